@@ -0,0 +1,214 @@
+//! Everything the game plays goes through here: the background music, and sound effects requested
+//! with [`PlaySfx`].
+//!
+//! Sound effects are loaded once at startup, pick one of their variants at random, and go through
+//! the volume bus of their [`SfxCategory`]. Each kind has a cap on how many can play at once, and
+//! they're despawned once they're done playing.
+
+use std::collections::HashMap;
+
+use bevy::{audio::Volume, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySfx>()
+            .add_systems(PreStartup, (Self::load_sfx, Self::start_background_music))
+            .add_systems(
+                Update,
+                Self::apply_music_volume.run_if(resource_changed::<Settings>),
+            )
+            // Last, so sounds requested from any schedule are played on the same frame
+            .add_systems(PostUpdate, Self::play_sfx);
+    }
+}
+
+/// Play a sound effect.
+#[derive(Debug, Event, Clone, Copy)]
+pub struct PlaySfx(pub SfxKind);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SfxKind {
+    Jump,
+    Walk,
+    Death,
+    Spike,
+    Flag,
+    Laser,
+    ButtonHover,
+    ButtonSelect,
+}
+
+/// Sound effects are grouped in categories, each with its own volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SfxCategory {
+    /// Sounds of the player moving
+    Player,
+    /// Goal, deaths and obstacles
+    World,
+    /// Menus
+    Interface,
+}
+
+impl SfxCategory {
+    pub const ALL: [SfxCategory; 3] = [
+        SfxCategory::Player,
+        SfxCategory::World,
+        SfxCategory::Interface,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SfxCategory::Player => "Player",
+            SfxCategory::World => "World",
+            SfxCategory::Interface => "Menus",
+        }
+    }
+}
+
+impl SfxKind {
+    const ALL: [SfxKind; 8] = [
+        SfxKind::Jump,
+        SfxKind::Walk,
+        SfxKind::Death,
+        SfxKind::Spike,
+        SfxKind::Flag,
+        SfxKind::Laser,
+        SfxKind::ButtonHover,
+        SfxKind::ButtonSelect,
+    ];
+
+    pub fn category(&self) -> SfxCategory {
+        match self {
+            SfxKind::Jump | SfxKind::Walk => SfxCategory::Player,
+            SfxKind::Death | SfxKind::Spike | SfxKind::Flag | SfxKind::Laser => SfxCategory::World,
+            SfxKind::ButtonHover | SfxKind::ButtonSelect => SfxCategory::Interface,
+        }
+    }
+
+    /// Files of the sound, one of them is picked at random each time it's played.
+    fn variants(&self) -> &'static [&'static str] {
+        match self {
+            SfxKind::Jump => &[
+                "sounds/jump.wav",
+                "sounds/gmtk2025_jump-001.ogg",
+                "sounds/gmtk2025_jump-002.ogg",
+            ],
+            SfxKind::Walk => &[
+                "sounds/gmtk2025_walk-001.ogg",
+                "sounds/gmtk2025_walk-002.ogg",
+                "sounds/gmtk2025_walk-003.ogg",
+            ],
+            SfxKind::Death => &["sounds/death.wav"],
+            SfxKind::Spike => &[
+                "sounds/gmtk2025_spike-001.ogg",
+                "sounds/gmtk2025_spike-002.ogg",
+            ],
+            SfxKind::Flag => &["sounds/flag.ogg"],
+            SfxKind::Laser => &[
+                "sounds/laser.wav",
+                "sounds/laser-001.ogg",
+                "sounds/laser-002.ogg",
+            ],
+            SfxKind::ButtonHover => &["sounds/button_hover.wav"],
+            SfxKind::ButtonSelect => &["sounds/button_select.wav"],
+        }
+    }
+
+    /// How many of this sound can play at the same time. Requests over it are dropped.
+    fn max_instances(&self) -> usize {
+        match self {
+            // Footsteps are requested every frame the player walks, so this also paces them
+            SfxKind::Walk => 1,
+            SfxKind::ButtonHover | SfxKind::ButtonSelect => 2,
+            _ => 4,
+        }
+    }
+}
+
+/// Marker for the music playing in the background, as opposed to sound effects.
+#[derive(Debug, Component)]
+pub struct BackgroundMusic;
+
+/// A sound effect currently playing
+#[derive(Debug, Component)]
+struct Sfx(SfxKind);
+
+/// Handles of every sound effect, loaded at startup.
+#[derive(Debug, Resource)]
+struct SfxLibrary(HashMap<SfxKind, Vec<Handle<AudioSource>>>);
+
+impl SoundPlugin {
+    fn load_sfx(mut commands: Commands, asset_server: Res<AssetServer>) {
+        commands.insert_resource(SfxLibrary(
+            SfxKind::ALL
+                .into_iter()
+                .map(|kind| {
+                    let handles = kind
+                        .variants()
+                        .iter()
+                        .map(|path| asset_server.load(*path))
+                        .collect();
+                    (kind, handles)
+                })
+                .collect(),
+        ));
+    }
+
+    fn start_background_music(
+        asset_server: Res<AssetServer>,
+        settings: Res<Settings>,
+        mut commands: Commands,
+    ) {
+        commands.spawn((
+            BackgroundMusic,
+            AudioPlayer::new(asset_server.load("sounds/bg_music.wav")),
+            PlaybackSettings::LOOP.with_volume(Volume::Linear(settings.music_volume)),
+        ));
+    }
+
+    fn apply_music_volume(
+        settings: Res<Settings>,
+        sinks: Query<&mut AudioSink, With<BackgroundMusic>>,
+    ) {
+        for mut sink in sinks {
+            sink.set_volume(Volume::Linear(settings.music_volume));
+        }
+    }
+
+    fn play_sfx(
+        mut commands: Commands,
+        mut sfx_reader: EventReader<PlaySfx>,
+        library: Res<SfxLibrary>,
+        settings: Res<Settings>,
+        playing: Query<&Sfx>,
+    ) {
+        let mut instances = HashMap::<SfxKind, usize>::new();
+        for Sfx(kind) in &playing {
+            *instances.entry(*kind).or_default() += 1;
+        }
+
+        for PlaySfx(kind) in sfx_reader.read() {
+            let count = instances.entry(*kind).or_default();
+            if *count >= kind.max_instances() {
+                continue;
+            }
+            let Some(variants) = library.0.get(kind).filter(|variants| !variants.is_empty()) else {
+                continue;
+            };
+            *count += 1;
+
+            let handle = variants[rand::random_range(0..variants.len())].clone();
+            let volume = settings.sfx_volume * settings.bus_volume(kind.category());
+            commands.spawn((
+                Sfx(*kind),
+                AudioPlayer::new(handle),
+                PlaybackSettings::DESPAWN.with_volume(Volume::Linear(volume)),
+            ));
+        }
+    }
+}
@@ -4,10 +4,15 @@ use bevy::{
         tonemapping::{DebandDither, Tonemapping},
     },
     prelude::*,
-    window::PrimaryWindow,
+    render::camera::ScalingMode,
 };
 
-use crate::{GameState, LevelDimensions, modes::GameMode, player::Player};
+use crate::{
+    GameState, LevelDimensions, VIEW_SIZE,
+    input::{Action, ActionInput, gamepad_right_stick},
+    modes::GameMode,
+    player::Player,
+};
 
 pub struct CameraPlugin;
 /// How many tiles ahead of the player the camera should be.
@@ -15,15 +20,20 @@ const CAMERA_AHEAD: usize = 15;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut bevy::app::App) {
-        app.add_systems(Startup, Self::spawn_camera).add_systems(
-            Update,
-            (
-                Self::follow_player
-                    .run_if(in_state(GameMode::Survive).or(in_state(GameMode::Replay))),
-                Self::keyboard_input.run_if(in_state(GameMode::Defend)),
+        app.add_systems(Startup, Self::spawn_camera)
+            .add_systems(
+                Update,
+                (
+                    Self::follow_player
+                        .run_if(in_state(GameMode::Survive).or(in_state(GameMode::Replay))),
+                    Self::keyboard_input.run_if(in_state(GameMode::Defend)),
+                )
+                    .run_if(in_state(GameState::Game)),
             )
-                .run_if(in_state(GameState::Game)),
-        );
+            .add_systems(
+                Update,
+                Self::keyboard_input.run_if(in_state(GameState::Editor)),
+            );
     }
 }
 
@@ -38,6 +48,14 @@ impl CameraPlugin {
                 hdr: true,
                 ..Default::default()
             },
+            // Always show the same part of the world, whatever the size of the window
+            Projection::from(OrthographicProjection {
+                scaling_mode: ScalingMode::AutoMin {
+                    min_width: VIEW_SIZE.x,
+                    min_height: VIEW_SIZE.y,
+                },
+                ..OrthographicProjection::default_2d()
+            }),
             Tonemapping::BlenderFilmic,
             bloom,
             DebandDither::Enabled,
@@ -46,19 +64,17 @@ impl CameraPlugin {
 
     /// Follow the player smoothly
     fn follow_player(
-        mut camera: Single<&mut Transform, (With<Camera2d>, Without<Player>)>,
+        camera: Single<(&mut Transform, &Projection), (With<Camera2d>, Without<Player>)>,
         player: Single<&Transform, (With<Player>, Without<Camera2d>)>,
         level_dimensions: Res<LevelDimensions>,
-        main_window: Single<&Window, With<PrimaryWindow>>,
     ) {
+        let (mut camera, projection) = camera.into_inner();
         let target_translation = vec3(
-            (player.translation.x + level_dimensions.tile_size * CAMERA_AHEAD as f32)
-                .max(level_dimensions.start.x + main_window.size().x / 2.) // Clamp to the level size
-                .min(
-                    level_dimensions.start.x
-                        + level_dimensions.tile_size * level_dimensions.level_length as f32
-                        - main_window.size().x / 2.,
-                ),
+            Self::clamp_to_level(
+                player.translation.x + level_dimensions.tile_size * CAMERA_AHEAD as f32,
+                &level_dimensions,
+                projection,
+            ),
             0.,
             0.,
         );
@@ -74,35 +90,44 @@ impl CameraPlugin {
         camera.translation += dir * f32::min(10., diff_length);
     }
 
-    /// Move the camera with keyboard on defend mode
+    /// Move the camera with keyboard (or the right stick) on defend mode and in the editor
     fn keyboard_input(
+        input: ActionInput,
         keyboard: Res<ButtonInput<KeyCode>>,
-        mut camera: Single<&mut Transform, With<Camera2d>>,
+        gamepads: Query<&Gamepad>,
+        camera: Single<(&mut Transform, &Projection), With<Camera2d>>,
         level_dimensions: Res<LevelDimensions>,
-        main_window: Single<&Window, With<PrimaryWindow>>,
     ) {
-        if keyboard.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]) {
-            camera.translation.x -= 10.;
-        }
-        if keyboard.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]) {
-            camera.translation.x += 10.;
-        }
-        if keyboard.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]) {
-            camera.translation.y += 10.;
-        }
-        if keyboard.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]) {
-            camera.translation.y -= 10.;
+        let (mut camera, projection) = camera.into_inner();
+        // Ctrl is for shortcuts, like saving in the editor with Ctrl+S
+        if !keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+            if input.pressed(Action::CameraLeft) {
+                camera.translation.x -= 10.;
+            }
+            if input.pressed(Action::CameraRight) {
+                camera.translation.x += 10.;
+            }
+            if input.pressed(Action::CameraUp) {
+                camera.translation.y += 10.;
+            }
+            if input.pressed(Action::CameraDown) {
+                camera.translation.y -= 10.;
+            }
         }
+        camera.translation += (gamepad_right_stick(&gamepads) * 10.).extend(0.);
 
-        camera.translation.x = camera
-            .translation
-            .x
-            .max(level_dimensions.start.x + main_window.size().x / 2.) // Clamp to the level size
-            .min(
-                level_dimensions.start.x
-                    + level_dimensions.tile_size * level_dimensions.level_length as f32
-                    - main_window.size().x / 2.,
-            );
+        camera.translation.x =
+            Self::clamp_to_level(camera.translation.x, &level_dimensions, projection);
         camera.translation.y = camera.translation.y.max(0.0).min(100.);
     }
+
+    /// Keep the camera inside the level, given how much of the world it shows.
+    fn clamp_to_level(x: f32, level_dimensions: &LevelDimensions, projection: &Projection) -> f32 {
+        let half_width = match projection {
+            Projection::Orthographic(orthographic) => orthographic.area.width() / 2.,
+            _ => VIEW_SIZE.x / 2.,
+        };
+        x.max(level_dimensions.start.x + half_width)
+            .min(level_dimensions.start.x + level_dimensions.width() - half_width)
+    }
 }
@@ -0,0 +1,214 @@
+//! A campaign of hand-made levels, each one unlocked by clearing the one before it.
+//!
+//! A level is cleared once it was beaten, and its replay stopped, a few times in a row. The best
+//! score on every level is kept along with how far the campaign went.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState, LevelId,
+    level::{CurrentLevel, DEFAULT_LEVEL_PATH, LevelAsset},
+    modes::{GameMode, Restarting},
+    score::Score,
+    storage,
+};
+
+/// Where the campaign progress is saved, inside the data directory.
+const PROGRESS_FILE: &str = "campaign.ron";
+
+/// A level of the campaign.
+#[derive(Debug, Clone, Copy)]
+pub struct CampaignLevel {
+    /// Path of the level file, inside the assets
+    pub path: &'static str,
+    /// Loops to play to clear the level
+    pub loops: u32,
+}
+
+/// Levels of the campaign, in the order they're unlocked.
+pub const CAMPAIGN: [CampaignLevel; 5] = [
+    CampaignLevel {
+        path: DEFAULT_LEVEL_PATH,
+        loops: 1,
+    },
+    CampaignLevel {
+        path: "levels/campaign/02-mind-the-gap.level.ron",
+        loops: 2,
+    },
+    CampaignLevel {
+        path: "levels/campaign/03-stairway.level.ron",
+        loops: 2,
+    },
+    CampaignLevel {
+        path: "levels/campaign/04-low-ceiling.level.ron",
+        loops: 3,
+    },
+    CampaignLevel {
+        path: "levels/campaign/05-over-the-pit.level.ron",
+        loops: 3,
+    },
+];
+
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CampaignProgress::load())
+            .init_resource::<CampaignRun>()
+            .add_event::<StartCampaignLevel>()
+            .add_systems(Startup, Self::load_levels)
+            .add_systems(
+                Update,
+                Self::start_level.run_if(on_event::<StartCampaignLevel>),
+            )
+            .add_systems(
+                OnTransition {
+                    exited: GameMode::Replay,
+                    entered: GameMode::Survive,
+                },
+                // Restarting isn't the end of a loop
+                Self::end_loop
+                    .run_if(in_state(GameState::Game).and(not(resource_exists::<Restarting>))),
+            )
+            .add_systems(OnEnter(GameState::GameOver), Self::record_best_score);
+    }
+}
+
+/// Play a level of the campaign, by its index in [`CAMPAIGN`], if it's unlocked.
+#[derive(Debug, Event)]
+pub struct StartCampaignLevel(pub usize);
+
+/// The levels of the campaign, loaded up front to show their names.
+#[derive(Debug, Resource)]
+pub struct CampaignLevels(pub Vec<Handle<LevelAsset>>);
+
+/// Index in [`CAMPAIGN`] of the level being played, if the current run is part of the campaign.
+#[derive(Debug, Resource, Default)]
+pub struct CampaignRun(pub Option<usize>);
+
+/// How far the campaign went, saved between sessions.
+#[derive(Debug, Resource, Default, Serialize, Deserialize)]
+pub struct CampaignProgress {
+    /// How many levels were cleared, in order
+    pub cleared: usize,
+    /// Best score on each level, by path
+    pub best_scores: HashMap<String, u32>,
+}
+
+impl CampaignProgress {
+    fn load() -> Self {
+        storage::load_ron(storage::data_path(PROGRESS_FILE)).unwrap_or_else(|err| {
+            info!("No campaign progress loaded: {err}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) {
+        if let Err(err) = storage::save_ron(storage::data_path(PROGRESS_FILE), self) {
+            error!("Failed to save the campaign progress: {err}");
+        }
+    }
+
+    /// Whether the level at `index` can be played: the first one always is, the next ones once
+    /// the level before them was cleared.
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        index <= self.cleared
+    }
+
+    pub fn best_score(&self, index: usize) -> Option<u32> {
+        self.best_scores.get(CAMPAIGN[index].path).copied()
+    }
+
+    /// Keep `points` if they're the best on the level at `index`, returning whether they are.
+    pub fn record_score(&mut self, index: usize, points: u32) -> bool {
+        if self.best_score(index).is_some_and(|best| best >= points) {
+            return false;
+        }
+        self.best_scores
+            .insert(CAMPAIGN[index].path.to_string(), points);
+        true
+    }
+
+    /// Clear the level at `index`, unlocking the one after it.
+    pub fn clear(&mut self, index: usize) {
+        self.cleared = self.cleared.max(index + 1);
+    }
+}
+
+impl CampaignPlugin {
+    fn load_levels(mut commands: Commands, asset_server: Res<AssetServer>) {
+        commands.insert_resource(CampaignLevels(
+            CAMPAIGN
+                .iter()
+                .map(|level| asset_server.load(level.path))
+                .collect(),
+        ));
+    }
+
+    fn start_level(
+        mut commands: Commands,
+        mut start_reader: EventReader<StartCampaignLevel>,
+        levels: Res<CampaignLevels>,
+        progress: Res<CampaignProgress>,
+        mut run: ResMut<CampaignRun>,
+        mut score: ResMut<Score>,
+        mut state: ResMut<NextState<GameState>>,
+    ) {
+        let Some(&StartCampaignLevel(index)) = start_reader.read().last() else {
+            return;
+        };
+        if !progress.is_unlocked(index) {
+            warn!("Level {} of the campaign is still locked", index + 1);
+            return;
+        }
+        info!("Starting level {} of the campaign", index + 1);
+
+        commands.insert_resource(CurrentLevel(levels.0[index].clone()));
+        commands.insert_resource(LevelId(CAMPAIGN[index].path.to_string()));
+        *score = Score::default();
+        run.0 = Some(index);
+        state.set(GameState::Game);
+    }
+
+    /// Once the replay was stopped enough times, the level is cleared and it's back to the level
+    /// select.
+    fn end_loop(
+        score: Res<Score>,
+        run: Res<CampaignRun>,
+        mut progress: ResMut<CampaignProgress>,
+        mut state: ResMut<NextState<GameState>>,
+    ) {
+        let Some(index) = run.0 else {
+            return;
+        };
+        if score.loops < CAMPAIGN[index].loops {
+            return;
+        }
+        info!(
+            "Level {} of the campaign cleared with {} points",
+            index + 1,
+            score.points()
+        );
+        progress.record_score(index, score.points());
+        progress.clear(index);
+        progress.save();
+        state.set(GameState::Menu);
+    }
+
+    /// Running out of lives doesn't clear the level, but the score still counts.
+    fn record_best_score(
+        score: Res<Score>,
+        run: Res<CampaignRun>,
+        mut progress: ResMut<CampaignProgress>,
+    ) {
+        let Some(index) = run.0 else {
+            return;
+        };
+        if progress.record_score(index, score.points()) {
+            progress.save();
+        }
+    }
+}
@@ -0,0 +1,364 @@
+//! A challenge that's the same for everyone on a given day: the level and the obstacles handed out
+//! in defend mode come from a seed made of the date.
+//!
+//! Only the first attempt of the day is scored. Results are kept in a local leaderboard, which can
+//! be exported to share it, and merged with the results other players exported.
+
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState, LevelId,
+    level::{CurrentLevel, LevelAsset, PlacedObstacle, TileRect},
+    modes::RestartRun,
+    obstacles::ObstacleType,
+    rng::GameRng,
+    score::Score,
+    storage,
+};
+
+/// Where the results of the daily challenges are saved, inside the data directory.
+const RESULTS_FILE: &str = "daily.ron";
+/// Where the local results are exported to, inside the data directory.
+const EXPORT_FILE: &str = "daily-export.ron";
+/// Results exported by other players go in this directory, inside the data directory.
+const IMPORT_DIR: &str = "daily-imports";
+
+/// Length of the daily level, in tiles
+const LEVEL_LENGTH: u32 = 75;
+/// Height of the ground, in tiles
+const GROUND_HEIGHT: i32 = 3;
+/// Tiles between two features of the daily level, enough to land after jumping over one
+const FEATURE_SPACING: std::ops::RangeInclusive<i32> = 9..=14;
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DailyChallenge>()
+            .insert_resource(DailyResults::load())
+            .add_event::<StartDailyChallenge>()
+            .add_event::<ExportDailyResults>()
+            .add_event::<ImportDailyResults>()
+            .add_systems(
+                Update,
+                (
+                    Self::start_daily.run_if(on_event::<StartDailyChallenge>),
+                    Self::export_results.run_if(on_event::<ExportDailyResults>),
+                    Self::import_results.run_if(on_event::<ImportDailyResults>),
+                ),
+            )
+            .add_systems(OnEnter(GameState::GameOver), Self::record_result)
+            // Restarting or leaving the challenge ends the attempt too
+            .add_systems(
+                FixedPreUpdate,
+                Self::record_result
+                    .run_if(on_event::<RestartRun>)
+                    .before(crate::update_state),
+            );
+    }
+}
+
+/// Play today's challenge, if it wasn't played yet.
+#[derive(Debug, Event)]
+pub struct StartDailyChallenge;
+
+/// Save the local results to share them.
+#[derive(Debug, Event)]
+pub struct ExportDailyResults;
+
+/// Merge in the results shared by other players.
+#[derive(Debug, Event)]
+pub struct ImportDailyResults;
+
+/// A day, in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        let days = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs() / 86_400);
+        Self::from_days_since_epoch(days as i64)
+    }
+
+    /// Convert days since 1970-01-01 to a date in the Gregorian calendar.
+    /// From <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = (year_of_era + era * 400 + i64::from(month <= 2)) as i32;
+        Self { year, month, day }
+    }
+
+    /// Seed of the day's challenge, which reads like the date (e.g. 20250131).
+    pub fn seed(&self) -> u64 {
+        self.year as u64 * 10_000 + self.month as u64 * 100 + self.day as u64
+    }
+}
+
+impl std::fmt::Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// The daily challenge being played, if the current run is one.
+#[derive(Debug, Resource, Default)]
+pub struct DailyChallenge(pub Option<Date>);
+
+impl DailyChallenge {
+    pub fn is_active(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub date: Date,
+    pub player: String,
+    pub points: u32,
+    pub loops: u32,
+    pub time_secs: f32,
+}
+
+/// Results of every daily challenge played here, or imported from other players.
+#[derive(Debug, Resource, Default, Serialize, Deserialize)]
+pub struct DailyResults {
+    pub entries: Vec<DailyResult>,
+}
+
+impl DailyResults {
+    fn load() -> Self {
+        storage::load_ron(storage::data_path(RESULTS_FILE)).unwrap_or_else(|err| {
+            info!("No daily results loaded: {err}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) {
+        if let Err(err) = storage::save_ron(storage::data_path(RESULTS_FILE), self) {
+            error!("Failed to save daily results: {err}");
+        }
+    }
+
+    /// Whether `player` already had their attempt at the challenge of `date`.
+    pub fn played(&self, date: Date, player: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.date == date && entry.player == player)
+    }
+
+    /// Results for the challenge of `date`, best first.
+    pub fn leaderboard(&self, date: Date) -> Vec<&DailyResult> {
+        let mut entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.date == date)
+            .collect();
+        entries.sort_by(|a, b| {
+            b.points
+                .cmp(&a.points)
+                .then(a.time_secs.total_cmp(&b.time_secs))
+        });
+        entries
+    }
+
+    /// Add results that aren't known yet, returning how many were added.
+    pub fn merge(&mut self, other: DailyResults) -> usize {
+        let mut added = 0;
+        for entry in other.entries {
+            if !self.played(entry.date, &entry.player) {
+                self.entries.push(entry);
+                added += 1;
+            }
+        }
+        added
+    }
+}
+
+/// Name the results of this computer are saved under.
+pub fn local_player() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "Player".to_string())
+}
+
+/// The level of the challenge of `date`: the ground, with spikes to jump over and
+/// platforms to climb on, far enough apart to deal with one at a time.
+pub fn daily_level(date: Date) -> LevelAsset {
+    let mut rng = GameRng::new(date.seed());
+    let start = (1, GROUND_HEIGHT);
+    let goal = (LEVEL_LENGTH as i32 - 10, GROUND_HEIGHT);
+
+    let mut platforms = Vec::new();
+    let mut obstacles = Vec::new();
+    let mut x = start.0 + rng.random_range(FEATURE_SPACING);
+    while x < goal.0 - 5 {
+        if rng.random_range(0..3) == 0 {
+            platforms.push(TileRect {
+                x,
+                y: GROUND_HEIGHT + rng.random_range(4..=6),
+                width: rng.random_range(3..=6),
+                height: 1,
+            });
+        } else {
+            obstacles.push(PlacedObstacle {
+                kind: ObstacleType::Spike,
+                position: (x, GROUND_HEIGHT),
+                patrol: None,
+            });
+        }
+        x += rng.random_range(FEATURE_SPACING);
+    }
+
+    LevelAsset {
+        name: format!("Daily {date}"),
+        length: LEVEL_LENGTH,
+        start,
+        goal,
+        ground: vec![TileRect {
+            x: 0,
+            y: 0,
+            width: LEVEL_LENGTH,
+            height: GROUND_HEIGHT as u32,
+        }],
+        platforms,
+        obstacles,
+    }
+}
+
+impl DailyPlugin {
+    fn start_daily(
+        mut commands: Commands,
+        mut start_reader: EventReader<StartDailyChallenge>,
+        mut levels: ResMut<Assets<LevelAsset>>,
+        mut results: ResMut<DailyResults>,
+        mut daily: ResMut<DailyChallenge>,
+        mut rng: ResMut<GameRng>,
+        mut score: ResMut<Score>,
+        mut state: ResMut<NextState<GameState>>,
+    ) {
+        start_reader.clear();
+        let date = Date::today();
+        let player = local_player();
+        if results.played(date, &player) {
+            warn!("The daily challenge of {date} was already played");
+            return;
+        }
+        info!("Starting the daily challenge of {date}");
+
+        // Saved right away, so quitting doesn't give another attempt
+        results.entries.push(DailyResult {
+            date,
+            player,
+            points: 0,
+            loops: 0,
+            time_secs: 0.,
+        });
+        results.save();
+
+        commands.insert_resource(CurrentLevel(levels.add(daily_level(date))));
+        commands.insert_resource(LevelId(format!("daily-{date}")));
+        rng.reseed(date.seed());
+        *score = Score::default();
+        daily.0 = Some(date);
+        state.set(GameState::Game);
+    }
+
+    /// Only the first attempt at the challenge counts, until a game over or a restart: trying
+    /// again is just practice.
+    pub(crate) fn record_result(
+        score: Res<Score>,
+        mut daily: ResMut<DailyChallenge>,
+        mut results: ResMut<DailyResults>,
+    ) {
+        let Some(date) = daily.0.take() else {
+            return;
+        };
+        let player = local_player();
+        let Some(entry) = results
+            .entries
+            .iter_mut()
+            .find(|entry| entry.date == date && entry.player == player)
+        else {
+            return;
+        };
+        entry.points = score.points();
+        entry.loops = score.loops;
+        entry.time_secs = score.time.as_secs_f32();
+        info!(
+            "Daily challenge of {date} done with {} points",
+            entry.points
+        );
+        results.save();
+    }
+
+    fn export_results(
+        mut export_reader: EventReader<ExportDailyResults>,
+        results: Res<DailyResults>,
+    ) {
+        export_reader.clear();
+        let player = local_player();
+        let export = DailyResults {
+            entries: results
+                .entries
+                .iter()
+                .filter(|entry| entry.player == player)
+                .cloned()
+                .collect(),
+        };
+        let path = storage::data_path(EXPORT_FILE);
+        match storage::save_ron(&path, &export) {
+            Ok(()) => info!("Exported daily results to {}", path.display()),
+            Err(err) => error!("Failed to export daily results: {err}"),
+        }
+    }
+
+    /// Merge every file in the import directory.
+    fn import_results(
+        mut import_reader: EventReader<ImportDailyResults>,
+        mut results: ResMut<DailyResults>,
+    ) {
+        import_reader.clear();
+        let dir = storage::data_path(IMPORT_DIR);
+        let files = match fs::read_dir(&dir) {
+            Ok(files) => files,
+            Err(err) => {
+                warn!("No daily results to import from {}: {err}", dir.display());
+                return;
+            }
+        };
+        let mut added = 0;
+        for file in files.flatten() {
+            let path = file.path();
+            match storage::load_ron::<DailyResults>(&path) {
+                Ok(imported) => added += results.merge(imported),
+                Err(err) => error!("Failed to import {}: {err}", path.display()),
+            }
+        }
+        info!("Imported {added} daily results from {}", dir.display());
+        results.save();
+    }
+}
@@ -0,0 +1,621 @@
+//! In-game level editor. Everything is snapped to the level's tile grid, and can be exported
+//! to a level file that's loaded like any other level.
+
+use bevy::{input::mouse::AccumulatedMouseScroll, prelude::*, window::PrimaryWindow};
+
+use crate::{
+    GameState, LevelDimensions, LevelId,
+    input::{Action, ActionInput},
+    level::{
+        CurrentLevel, DEFAULT_PLAYER_START, Level, LevelAsset, PlacedObstacle, PlacedPatrol,
+        TileRect,
+    },
+    obstacles::{
+        ObstacleRegistry, ObstacleType, get_cursor_world_pos,
+        saw::{DEFAULT_SAW_SPEED, MAX_SAW_SPEED, MIN_SAW_SPEED},
+    },
+    storage,
+};
+
+/// Asset path of the edited level, once exported.
+pub const CUSTOM_LEVEL_PATH: &str = "levels/custom.level.ron";
+/// Where the edited level is exported to. It's inside the assets, so it can be loaded right away.
+pub const EXPORT_PATH: &str = "assets/levels/custom.level.ron";
+
+const GROUND_COLOR: Color = Color::WHITE;
+const PLATFORM_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+const GOAL_COLOR: Color = Color::srgb(1.0, 1.0, 0.);
+const SPAWN_COLOR: Color = Color::srgb(0.2, 1.0, 0.3);
+const OBSTACLE_COLOR: Color = Color::srgb(1.0, 0.2, 0.3);
+const HOVER_COLOR: Color = Color::srgb(0.2, 0.8, 1.0);
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Editor),
+            (Self::start_editing, Self::spawn_help),
+        )
+        .add_systems(
+            Update,
+            (
+                Self::select_tool,
+                Self::update_hover,
+                Self::handle_mouse,
+                Self::resize_hovered,
+                Self::change_level_length,
+                Self::export_level,
+                Self::draw_level,
+                Self::exit_editor,
+            )
+                .chain()
+                .run_if(in_state(GameState::Editor)),
+        )
+        .add_systems(
+            OnExit(GameState::Editor),
+            (
+                Self::stop_editing,
+                crate::menu::despawn_screen::<EditorMarker>,
+            ),
+        );
+    }
+}
+
+/// What gets placed when clicking on an empty tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorTool {
+    Ground,
+    Platform,
+    Goal,
+    Spawn,
+    Obstacle(ObstacleType),
+}
+
+/// Something in the level being edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditorItem {
+    Ground(usize),
+    Platform(usize),
+    Goal,
+    Spawn,
+    Obstacle(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Drag {
+    /// Moving an item, holding it from `offset` tiles away from its anchor
+    Move { item: EditorItem, offset: IVec2 },
+    /// Stretching a ground or platform from the tile where the drag started
+    Stretch { item: EditorItem, from: IVec2 },
+    /// Moving the end of the path of the saw at this index
+    PatrolEnd(usize),
+}
+
+#[derive(Debug, Resource)]
+pub struct LevelEditor {
+    level: LevelAsset,
+    tool: EditorTool,
+    /// Tile under the cursor
+    cursor: Option<IVec2>,
+    hovered: Option<EditorItem>,
+    drag: Option<Drag>,
+}
+
+/// Marker for the editor's UI
+#[derive(Debug, Component)]
+struct EditorMarker;
+
+/// Text telling how the last export went
+#[derive(Debug, Component)]
+struct EditorStatus;
+
+impl LevelEditor {
+    fn item_at(&self, tile: IVec2) -> Option<EditorItem> {
+        let contains = |rect: &TileRect| {
+            (rect.x..rect.x + rect.width as i32).contains(&tile.x)
+                && (rect.y..rect.y + rect.height as i32).contains(&tile.y)
+        };
+
+        // Check the things drawn on top first
+        if let Some(index) = self
+            .level
+            .obstacles
+            .iter()
+            .position(|obstacle| IVec2::from(obstacle.position) == tile)
+        {
+            return Some(EditorItem::Obstacle(index));
+        }
+        if IVec2::from(self.level.start) == tile {
+            return Some(EditorItem::Spawn);
+        }
+        if contains(&TileRect {
+            x: self.level.goal.0,
+            y: self.level.goal.1,
+            width: 1,
+            height: 2,
+        }) {
+            return Some(EditorItem::Goal);
+        }
+        if let Some(index) = self.level.platforms.iter().position(contains) {
+            return Some(EditorItem::Platform(index));
+        }
+        self.level
+            .ground
+            .iter()
+            .position(contains)
+            .map(EditorItem::Ground)
+    }
+
+    fn rect_mut(&mut self, item: EditorItem) -> Option<&mut TileRect> {
+        match item {
+            EditorItem::Ground(index) => self.level.ground.get_mut(index),
+            EditorItem::Platform(index) => self.level.platforms.get_mut(index),
+            _ => None,
+        }
+    }
+
+    /// Bottom-left tile of an item, if it's still in the level
+    fn anchor(&self, item: EditorItem) -> Option<IVec2> {
+        match item {
+            EditorItem::Ground(index) => self
+                .level
+                .ground
+                .get(index)
+                .map(|rect| ivec2(rect.x, rect.y)),
+            EditorItem::Platform(index) => self
+                .level
+                .platforms
+                .get(index)
+                .map(|rect| ivec2(rect.x, rect.y)),
+            EditorItem::Goal => Some(self.level.goal.into()),
+            EditorItem::Spawn => Some(self.level.start.into()),
+            EditorItem::Obstacle(index) => self
+                .level
+                .obstacles
+                .get(index)
+                .map(|obstacle| obstacle.position.into()),
+        }
+    }
+
+    fn move_item(&mut self, item: EditorItem, to: IVec2) {
+        match item {
+            EditorItem::Ground(_) | EditorItem::Platform(_) => {
+                if let Some(rect) = self.rect_mut(item) {
+                    rect.x = to.x;
+                    rect.y = to.y;
+                }
+            }
+            EditorItem::Goal => self.level.goal = to.into(),
+            EditorItem::Spawn => self.level.start = to.into(),
+            EditorItem::Obstacle(index) => {
+                if let Some(obstacle) = self.level.obstacles.get_mut(index) {
+                    // The path of a saw moves along with it
+                    let shift = to - IVec2::from(obstacle.position);
+                    if let Some(patrol) = &mut obstacle.patrol {
+                        patrol.end = (IVec2::from(patrol.end) + shift).into();
+                    }
+                    obstacle.position = to.into();
+                }
+            }
+        }
+    }
+
+    fn remove_item(&mut self, item: EditorItem) {
+        // Removing shifts the indices after it, so whatever was dragged can't be found anymore
+        if !matches!(item, EditorItem::Goal | EditorItem::Spawn) {
+            self.drag = None;
+        }
+        match item {
+            EditorItem::Ground(index) => {
+                self.level.ground.remove(index);
+            }
+            EditorItem::Platform(index) => {
+                self.level.platforms.remove(index);
+            }
+            EditorItem::Obstacle(index) => {
+                self.level.obstacles.remove(index);
+            }
+            EditorItem::Goal | EditorItem::Spawn => {
+                info!("The level needs a goal and a spawn, move them instead");
+            }
+        }
+    }
+
+    /// Place something with the current tool, returning it.
+    fn place(&mut self, tile: IVec2) -> EditorItem {
+        let rect = TileRect {
+            x: tile.x,
+            y: tile.y,
+            width: 1,
+            height: 1,
+        };
+        match self.tool {
+            EditorTool::Ground => {
+                self.level.ground.push(rect);
+                EditorItem::Ground(self.level.ground.len() - 1)
+            }
+            EditorTool::Platform => {
+                self.level.platforms.push(rect);
+                EditorItem::Platform(self.level.platforms.len() - 1)
+            }
+            EditorTool::Goal => {
+                self.level.goal = tile.into();
+                EditorItem::Goal
+            }
+            EditorTool::Spawn => {
+                self.level.start = tile.into();
+                EditorItem::Spawn
+            }
+            EditorTool::Obstacle(kind) => {
+                self.level.obstacles.push(PlacedObstacle {
+                    kind,
+                    position: tile.into(),
+                    // Saws start out in place, until the end of their path is dragged away
+                    patrol: (kind == ObstacleType::Saw).then_some(PlacedPatrol {
+                        end: tile.into(),
+                        speed: DEFAULT_SAW_SPEED,
+                    }),
+                });
+                EditorItem::Obstacle(self.level.obstacles.len() - 1)
+            }
+        }
+    }
+}
+
+impl EditorPlugin {
+    fn start_editing(mut commands: Commands, level: Level) {
+        let level = level.get().cloned().unwrap_or_else(|| LevelAsset {
+            name: "Custom".to_string(),
+            length: 75,
+            start: DEFAULT_PLAYER_START,
+            goal: (65, 3),
+            ground: vec![TileRect {
+                x: 0,
+                y: 0,
+                width: 75,
+                height: 3,
+            }],
+            platforms: Vec::new(),
+            obstacles: Vec::new(),
+        });
+
+        commands.insert_resource(LevelEditor {
+            level,
+            tool: EditorTool::Ground,
+            cursor: None,
+            hovered: None,
+            drag: None,
+        });
+    }
+
+    fn stop_editing(mut commands: Commands) {
+        commands.remove_resource::<LevelEditor>();
+    }
+
+    fn spawn_help(mut commands: Commands, asset_server: Res<AssetServer>) {
+        commands.spawn((
+            EditorMarker,
+            Text::new(
+                "[1] Ground  [2] Platform  [3] Goal  [4] Spawn  [5] Spike  [6] Laser  [7] Saw\n\
+                 [LMB] Place / move / stretch  [RMB] Delete  [Scroll] Resize (hold shift for height)\n\
+                 [SHIFT+LMB] Drag the end of a saw's path  [Scroll] Speed of a saw\n\
+                 [-] [=] Level length  [WASD] Move camera  [CTRL+S] Export  [ESC] Back to menu",
+            ),
+            TextFont {
+                font_size: 18.,
+                font: asset_server.load("fonts/capitolcity.ttf"),
+                ..Default::default()
+            },
+            Node {
+                margin: UiRect::all(Val::Px(10.)),
+                ..Default::default()
+            },
+        ));
+        commands.spawn((
+            EditorMarker,
+            EditorStatus,
+            Text::default(),
+            TextFont {
+                font_size: 18.,
+                font: asset_server.load("fonts/capitolcity.ttf"),
+                ..Default::default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.),
+                left: Val::Px(10.),
+                ..Default::default()
+            },
+        ));
+    }
+
+    fn select_tool(keyboard: Res<ButtonInput<KeyCode>>, mut editor: ResMut<LevelEditor>) {
+        let tools = [
+            (KeyCode::Digit1, EditorTool::Ground),
+            (KeyCode::Digit2, EditorTool::Platform),
+            (KeyCode::Digit3, EditorTool::Goal),
+            (KeyCode::Digit4, EditorTool::Spawn),
+            (KeyCode::Digit5, EditorTool::Obstacle(ObstacleType::Spike)),
+            (KeyCode::Digit6, EditorTool::Obstacle(ObstacleType::Laser)),
+            (KeyCode::Digit7, EditorTool::Obstacle(ObstacleType::Saw)),
+        ];
+        for (key, tool) in tools {
+            if keyboard.just_pressed(key) {
+                info!("Editor tool: {tool:?}");
+                editor.tool = tool;
+            }
+        }
+    }
+
+    fn update_hover(
+        mut editor: ResMut<LevelEditor>,
+        window: Single<&Window, With<PrimaryWindow>>,
+        camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+        level_dimensions: Res<LevelDimensions>,
+    ) {
+        let (camera, camera_transform) = camera.into_inner();
+        editor.cursor = get_cursor_world_pos(window.into_inner(), camera, camera_transform)
+            .map(|pos| level_dimensions.pixels_to_grid_pos(pos).into());
+        let hovered = editor.cursor.and_then(|tile| editor.item_at(tile));
+        editor.hovered = hovered;
+    }
+
+    fn handle_mouse(
+        mouse: Res<ButtonInput<MouseButton>>,
+        keyboard: Res<ButtonInput<KeyCode>>,
+        mut editor: ResMut<LevelEditor>,
+    ) {
+        let Some(cursor) = editor.cursor else {
+            return;
+        };
+
+        if mouse.just_pressed(MouseButton::Right) {
+            if let Some(item) = editor.hovered {
+                editor.remove_item(item);
+                editor.hovered = None;
+            }
+        }
+
+        let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        if mouse.just_pressed(MouseButton::Left) && shift {
+            if let Some(EditorItem::Obstacle(index)) = editor.hovered {
+                let has_patrol = editor
+                    .level
+                    .obstacles
+                    .get(index)
+                    .is_some_and(|obstacle| obstacle.patrol.is_some());
+                if has_patrol {
+                    editor.drag = Some(Drag::PatrolEnd(index));
+                }
+            }
+        } else if mouse.just_pressed(MouseButton::Left) {
+            let hovered = editor
+                .hovered
+                .and_then(|item| Some((item, editor.anchor(item)?)));
+            editor.drag = Some(match hovered {
+                Some((item, anchor)) => Drag::Move {
+                    item,
+                    offset: cursor - anchor,
+                },
+                None => {
+                    let item = editor.place(cursor);
+                    match item {
+                        EditorItem::Ground(_) | EditorItem::Platform(_) => {
+                            Drag::Stretch { item, from: cursor }
+                        }
+                        _ => Drag::Move {
+                            item,
+                            offset: IVec2::ZERO,
+                        },
+                    }
+                }
+            });
+        } else if mouse.just_released(MouseButton::Left) {
+            editor.drag = None;
+        }
+
+        let drag = editor.drag;
+        match drag {
+            Some(Drag::Move { item, offset }) => editor.move_item(item, cursor - offset),
+            Some(Drag::Stretch { item, from }) => {
+                if let Some(rect) = editor.rect_mut(item) {
+                    let min = from.min(cursor);
+                    let size = (from - cursor).abs() + IVec2::ONE;
+                    *rect = TileRect {
+                        x: min.x,
+                        y: min.y,
+                        width: size.x as u32,
+                        height: size.y as u32,
+                    };
+                }
+            }
+            Some(Drag::PatrolEnd(index)) => {
+                if let Some(patrol) = editor
+                    .level
+                    .obstacles
+                    .get_mut(index)
+                    .and_then(|obstacle| obstacle.patrol.as_mut())
+                {
+                    patrol.end = cursor.into();
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn resize_hovered(
+        scroll: Res<AccumulatedMouseScroll>,
+        keyboard: Res<ButtonInput<KeyCode>>,
+        mut editor: ResMut<LevelEditor>,
+    ) {
+        let Some(item) = editor.hovered else {
+            return;
+        };
+        if scroll.delta.y == 0. {
+            return;
+        }
+        let change = scroll.delta.y.signum() as i32;
+        let change_height = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+        if let EditorItem::Obstacle(index) = item {
+            if let Some(patrol) = editor
+                .level
+                .obstacles
+                .get_mut(index)
+                .and_then(|obstacle| obstacle.patrol.as_mut())
+            {
+                patrol.speed = (patrol.speed + scroll.delta.y.signum() * 0.5)
+                    .clamp(MIN_SAW_SPEED, MAX_SAW_SPEED);
+                info!("Saw speed set to {} units/tick", patrol.speed);
+            }
+        }
+        if let Some(rect) = editor.rect_mut(item) {
+            let dimension = if change_height {
+                &mut rect.height
+            } else {
+                &mut rect.width
+            };
+            *dimension = dimension.saturating_add_signed(change).max(1);
+        }
+    }
+
+    fn change_level_length(keyboard: Res<ButtonInput<KeyCode>>, mut editor: ResMut<LevelEditor>) {
+        if keyboard.just_pressed(KeyCode::Minus) {
+            editor.level.length = editor.level.length.saturating_sub(5).max(10);
+        }
+        if keyboard.just_pressed(KeyCode::Equal) {
+            editor.level.length += 5;
+        }
+    }
+
+    /// Save the level, and make it the one that gets played.
+    fn export_level(
+        mut commands: Commands,
+        keyboard: Res<ButtonInput<KeyCode>>,
+        editor: Res<LevelEditor>,
+        asset_server: Res<AssetServer>,
+        mut status: Single<&mut Text, With<EditorStatus>>,
+    ) {
+        if !(keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+            && keyboard.just_pressed(KeyCode::KeyS))
+        {
+            return;
+        }
+        let last_tile = editor.level.length as i32 - 1;
+        if !(0..=last_tile).contains(&editor.level.goal.0)
+            || !(0..=last_tile).contains(&editor.level.start.0)
+        {
+            status.0 = format!(
+                "Not exported: the goal and spawn must be inside the level ({} tiles)",
+                editor.level.length
+            );
+            warn!("{}", status.0);
+            return;
+        }
+
+        match storage::save_ron(EXPORT_PATH, &editor.level) {
+            Ok(()) => {
+                status.0 = format!("Exported level to {EXPORT_PATH}");
+                info!("{}", status.0);
+            }
+            Err(err) => {
+                status.0 = format!("Failed to export level: {err}");
+                error!("{}", status.0);
+                return;
+            }
+        }
+
+        commands.insert_resource(CurrentLevel(asset_server.load(CUSTOM_LEVEL_PATH)));
+        commands.insert_resource(LevelId(CUSTOM_LEVEL_PATH.to_string()));
+        // In case it was already loaded before this export
+        asset_server.reload(CUSTOM_LEVEL_PATH);
+    }
+
+    fn draw_level(
+        editor: Res<LevelEditor>,
+        level_dimensions: Res<LevelDimensions>,
+        registry: Res<ObstacleRegistry>,
+        mut gizmos: Gizmos,
+    ) {
+        let tile_size = level_dimensions.tile_size;
+        let tile_rect = |x: i32, y: i32, width: u32, height: u32| {
+            let size = vec2(width as f32, height as f32) * tile_size;
+            (level_dimensions.grid_pos_to_pixels((x, y), size), size)
+        };
+        let color_of = |item: EditorItem, color: Color| {
+            if editor.hovered == Some(item) {
+                HOVER_COLOR
+            } else {
+                color
+            }
+        };
+
+        // Level bounds
+        let (center, size) = tile_rect(0, 0, editor.level.length, 40);
+        gizmos.rect_2d(center, size, Color::srgb(0.3, 0.3, 0.3));
+
+        for (index, rect) in editor.level.ground.iter().enumerate() {
+            let (center, size) = tile_rect(rect.x, rect.y, rect.width, rect.height);
+            gizmos.rect_2d(
+                center,
+                size,
+                color_of(EditorItem::Ground(index), GROUND_COLOR),
+            );
+        }
+        for (index, rect) in editor.level.platforms.iter().enumerate() {
+            let (center, size) = tile_rect(rect.x, rect.y, rect.width, rect.height);
+            gizmos.rect_2d(
+                center,
+                size,
+                color_of(EditorItem::Platform(index), PLATFORM_COLOR),
+            );
+        }
+
+        let (center, size) = tile_rect(editor.level.goal.0, editor.level.goal.1, 1, 2);
+        gizmos.rect_2d(center, size, color_of(EditorItem::Goal, GOAL_COLOR));
+        let (center, size) = tile_rect(editor.level.start.0, editor.level.start.1, 1, 1);
+        gizmos.rect_2d(center, size, color_of(EditorItem::Spawn, SPAWN_COLOR));
+
+        for (index, obstacle) in editor.level.obstacles.iter().enumerate() {
+            let (center, size) = tile_rect(obstacle.position.0, obstacle.position.1, 1, 1);
+            let color = color_of(EditorItem::Obstacle(index), OBSTACLE_COLOR);
+            // Obstacles are drawn from the bottom of their tile, where they're spawned
+            registry.get(obstacle.kind).draw_preview(
+                &mut gizmos,
+                center - vec2(0., size.y / 2.),
+                color,
+            );
+            if let Some(patrol) = obstacle.patrol {
+                let (end, _) = tile_rect(patrol.end.0, patrol.end.1, 1, 1);
+                let offset = vec2(0., size.y / 2.);
+                gizmos.line_2d(center - offset, end - offset, color.with_alpha(0.4));
+                registry.get(obstacle.kind).draw_preview(
+                    &mut gizmos,
+                    end - offset,
+                    color.with_alpha(0.4),
+                );
+            }
+        }
+
+        // Preview of what would be placed, like the ghost obstacles in defend mode
+        if let (Some(cursor), None, None) = (editor.cursor, editor.hovered, editor.drag) {
+            let (center, size) = tile_rect(cursor.x, cursor.y, 1, 1);
+            let color = Color::srgba(1.0, 1.0, 1.0, 0.4);
+            match editor.tool {
+                EditorTool::Obstacle(kind) => registry.get(kind).draw_preview(
+                    &mut gizmos,
+                    center - vec2(0., size.y / 2.),
+                    color,
+                ),
+                _ => gizmos.rect_2d(center, size, color),
+            }
+        }
+    }
+
+    fn exit_editor(input: ActionInput, mut app_state: ResMut<NextState<GameState>>) {
+        if input.just_pressed(Action::Pause) {
+            app_state.set(GameState::Menu);
+        }
+    }
+}
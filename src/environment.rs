@@ -1,49 +1,169 @@
 use avian2d::prelude::*;
 use bevy::prelude::*;
 
-use crate::{GameState, LevelDimensions, modes::GoalReached, player::Player};
+use crate::{
+    GameState, LevelDimensions,
+    level::{Level, LevelAsset, TileRect},
+    modes::GoalReached,
+    obstacles::{ObstacleRegistry, ObstacleSpawner, ObstacleType, saw::Patrol, spawn_obstacle},
+    player::{Player, past_runs::PastRun},
+};
 
 pub struct EnvironmentPlugin;
 
 #[derive(Debug, Event)]
 pub struct ResetEnvironment;
 
+/// Marker for everything that was spawned from the level file, so it can be respawned when it changes.
+#[derive(Debug, Component, Clone)]
+pub struct LevelGeometry;
+
+/// Whether the level changed since it was last spawned, so it's respawned once back in the game.
+#[derive(Debug, Resource, Default)]
+struct LevelOutdated(bool);
+
 impl Plugin for EnvironmentPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            OnEnter(GameState::Game),
-            (Self::spawn_ground, Self::spawn_goal, Self::spawn_walls),
-        )
-        .add_event::<ResetEnvironment>();
+        app.init_resource::<LevelOutdated>()
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Menu,
+                    entered: GameState::Game,
+                },
+                Self::spawn_level,
+            )
+            .add_systems(
+                Update,
+                (
+                    // In every state, so changes made while paused or in the menu aren't missed
+                    Self::track_level_changes,
+                    Self::spawn_level.run_if(
+                        in_state(GameState::Game).and(|outdated: Res<LevelOutdated>| outdated.0),
+                    ),
+                )
+                    .chain(),
+            )
+            .add_event::<ResetEnvironment>();
     }
 }
 
 impl EnvironmentPlugin {
-    fn spawn_ground(mut commands: Commands, level_dimensions: Res<LevelDimensions>) {
-        let ground_width = level_dimensions.tile_size * level_dimensions.level_length as f32;
+    /// Remember when the current level finished loading or was modified on disk.
+    fn track_level_changes(
+        mut asset_events: EventReader<AssetEvent<LevelAsset>>,
+        level: Level,
+        mut outdated: ResMut<LevelOutdated>,
+    ) {
+        if asset_events.read().any(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+                *id == level.id()
+            }
+            _ => false,
+        }) {
+            outdated.0 = true;
+        }
+    }
+
+    /// Spawn the level's geometry, replacing whatever was spawned from it before.
+    pub(crate) fn spawn_level(
+        mut commands: Commands,
+        level: Level,
+        mut level_dimensions: ResMut<LevelDimensions>,
+        previous_geometry: Query<Entity, With<LevelGeometry>>,
+        mut meshes: ResMut<Assets<Mesh>>,
+        mut materials: ResMut<Assets<ColorMaterial>>,
+        registry: Res<ObstacleRegistry>,
+        mut reset_environment: EventWriter<ResetEnvironment>,
+        mut outdated: ResMut<LevelOutdated>,
+    ) {
+        outdated.0 = false;
+        let Some(level) = level.get() else {
+            warn!("Level isn't loaded yet, it will be spawned once it is");
+            return;
+        };
+        info!("Spawning level `{}`", level.name);
+
+        for entity in &previous_geometry {
+            commands.entity(entity).despawn();
+        }
+        level_dimensions.set_level_length(level.length);
+
+        for ground in &level.ground {
+            Self::spawn_block(&mut commands, &level_dimensions, ground, Color::WHITE);
+        }
+        for platform in &level.platforms {
+            Self::spawn_block(
+                &mut commands,
+                &level_dimensions,
+                platform,
+                Color::srgb(0.8, 0.8, 0.8),
+            );
+        }
+        Self::spawn_goal(&mut commands, &level_dimensions, level.goal);
+        Self::spawn_walls(&mut commands, &level_dimensions);
+
+        let mut spawner = ObstacleSpawner {
+            commands: &mut commands,
+            meshes: &mut meshes,
+            materials: &mut materials,
+        };
+        let on_tile = |tile: (i32, i32)| {
+            level_dimensions.grid_pos_to_pixels(tile, vec2(level_dimensions.tile_size, 0.))
+        };
+        for obstacle in &level.obstacles {
+            let position = on_tile(obstacle.position);
+            let entity = spawn_obstacle(
+                &mut spawner,
+                &registry,
+                obstacle.kind,
+                position,
+                LevelGeometry,
+            );
+            if let (ObstacleType::Saw, Some(patrol)) = (obstacle.kind, obstacle.patrol) {
+                spawner.commands.entity(entity).insert(Patrol {
+                    start: position,
+                    end: on_tile(patrol.end),
+                    speed: patrol.speed,
+                });
+            }
+        }
+
+        // Things might have moved, so put the player back on the start
+        reset_environment.write(ResetEnvironment);
+    }
+
+    fn spawn_block(
+        commands: &mut Commands,
+        level_dimensions: &LevelDimensions,
+        rect: &TileRect,
+        color: Color,
+    ) {
+        let size = vec2(rect.width as f32, rect.height as f32) * level_dimensions.tile_size;
 
         commands.spawn((
+            LevelGeometry,
             Sprite {
-                color: Color::WHITE,
-                custom_size: Some(vec2(ground_width, level_dimensions.tile_size * 3.)),
+                color,
+                custom_size: Some(size),
                 ..Default::default()
             },
             RigidBody::Static,
-            Collider::rectangle(ground_width, level_dimensions.tile_size * 3.),
+            Collider::rectangle(size.x, size.y),
             Transform::from_translation(
                 level_dimensions
-                    .grid_pos_to_pixels((0, 0), vec2(ground_width, level_dimensions.tile_size * 3.))
+                    .grid_pos_to_pixels((rect.x, rect.y), size)
                     .extend(0.),
             ),
         ));
     }
 
-    fn spawn_goal(mut commands: Commands, level_dimensions: Res<LevelDimensions>) {
+    fn spawn_goal(commands: &mut Commands, level_dimensions: &LevelDimensions, pos: (i32, i32)) {
         let size = vec2(level_dimensions.tile_size, level_dimensions.tile_size * 2.);
 
         commands
             .spawn((
                 Goal,
+                LevelGeometry,
                 Sprite {
                     color: Color::srgb(1.0, 1.0, 0.),
                     custom_size: Some(size),
@@ -53,28 +173,27 @@ impl EnvironmentPlugin {
                 Sensor,
                 Collider::rectangle(size.x, size.y),
                 Transform::from_translation(
-                    level_dimensions
-                        .grid_pos_to_pixels((level_dimensions.level_length as i32 - 10, 3), size)
-                        .extend(0.),
+                    level_dimensions.grid_pos_to_pixels(pos, size).extend(0.),
                 ),
             ))
             .observe(
                 |trigger: Trigger<OnCollisionStart>,
-                 player_query: Query<(), With<Player>>,
+                 runner_query: Query<(), Or<(With<Player>, With<PastRun>)>>,
                  mut flag_event_writer: EventWriter<GoalReached>| {
-                    // if it's the player that collided, send the event
-                    if player_query.contains(trigger.collider) {
+                    // if it's the player or one of their past runs that collided, send the event
+                    if runner_query.contains(trigger.collider) {
                         flag_event_writer.write(GoalReached);
                     }
                 },
             );
     }
 
-    fn spawn_walls(mut commands: Commands, level_dimensions: Res<LevelDimensions>) {
+    fn spawn_walls(commands: &mut Commands, level_dimensions: &LevelDimensions) {
         let size = vec2(level_dimensions.tile_size, level_dimensions.tile_size * 40.);
 
         for x in [-1, level_dimensions.level_length as i32] {
             commands.spawn((
+                LevelGeometry,
                 Collider::rectangle(size.x, size.y),
                 RigidBody::Static,
                 Transform::from_translation(
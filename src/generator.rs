@@ -0,0 +1,682 @@
+//! Courses generated from a seed, to play something new without making levels by hand.
+//!
+//! A course is laid out from left to right: ground with gaps to jump over, stairs, platforms
+//! floating over pits, ceilings to hop under and spikes, bigger and more of them the harder it is.
+//! Each course is checked before it's played, by simulating the player's jumps tick by tick with
+//! the same rules as its movement: if the goal can't be reached from the start, another course is
+//! generated.
+
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+
+use crate::{
+    GRAVITY, GameState, LevelId, TILE_SIZE,
+    campaign::CampaignRun,
+    daily::DailyChallenge,
+    environment::EnvironmentPlugin,
+    level::{CurrentLevel, DEFAULT_LEVEL_PATH, LevelAsset, PlacedObstacle, TileRect},
+    obstacles::ObstacleType,
+    player::{
+        JUMP_HOLD_BOOST, PLAYER_ACCELERATION, PLAYER_DAMPING, PLAYER_GRAVITY_SCALE,
+        PLAYER_JUMP_IMPULSE, PLAYER_SIZE,
+    },
+    rng::GameRng,
+    settings::Settings,
+};
+
+/// Tiles after which no new feature starts. The course ends once the last one is done, with
+/// [`END_RUN`] tiles of flat ground to the goal and past it.
+pub const COURSE_LENGTH: u32 = 100;
+/// Courses generated from a seed until a solvable one is found
+const MAX_ATTEMPTS: u32 = 20;
+/// Flat ground to get going, in tiles
+const START_RUN: i32 = 8;
+/// Flat ground at the end of the course, in tiles
+const END_RUN: i32 = 16;
+/// How far the goal is from the end of the course, in tiles
+const GOAL_FROM_END: i32 = 10;
+/// Height of the ground where the player starts, in tiles
+const START_HEIGHT: i32 = 3;
+/// Heights the ground and the platforms stay within, so they fit in the view
+const HEIGHTS: RangeInclusive<i32> = 2..=16;
+/// Lowest ceiling a spike can be hopped over under, in free tiles
+const MIN_SPIKE_CLEARANCE: i32 = 5;
+/// Tiles of the player's best jump that are kept as a margin, so no jump has to be pixel perfect
+const REACH_MARGIN: i32 = 2;
+/// How many ticks the solver holds the jump for, the last one holding it all the way up
+const HOLD_TICKS: [u32; 8] = [0, 2, 4, 7, 11, 16, 24, u32::MAX];
+/// Longest a simulated jump stays in the air, in ticks
+const MAX_AIR_TICKS: u32 = 300;
+/// Boxes closer than this are only touching, to make up for rounding errors
+const TOUCHING: Vec2 = Vec2::splat(0.01);
+/// Level ids of generated courses start with this
+const GENERATED_LEVEL_ID: &str = "generated";
+
+pub struct GeneratorPlugin;
+
+impl Plugin for GeneratorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnTransition {
+                exited: GameState::Menu,
+                entered: GameState::Game,
+            },
+            Self::pick_course.before(EnvironmentPlugin::spawn_level),
+        );
+    }
+}
+
+/// How the player moves, to tell which jumps can be made. Distances are in world units.
+#[derive(Debug, Clone, Copy)]
+pub struct JumpPhysics {
+    pub acceleration: f32,
+    /// Horizontal speed kept from one tick to the next
+    pub damping: f32,
+    pub jump_impulse: f32,
+    /// Share of the jump impulse added every tick the jump is held on the way up
+    pub hold_boost: f32,
+    /// Already scaled by the character's gravity scale
+    pub gravity: f32,
+    pub size: Vec2,
+    /// Length of a tick, in seconds
+    pub timestep: f32,
+}
+
+impl Default for JumpPhysics {
+    /// The player's.
+    fn default() -> Self {
+        Self {
+            acceleration: PLAYER_ACCELERATION,
+            damping: PLAYER_DAMPING,
+            jump_impulse: PLAYER_JUMP_IMPULSE,
+            hold_boost: JUMP_HOLD_BOOST,
+            gravity: GRAVITY * PLAYER_GRAVITY_SCALE,
+            size: PLAYER_SIZE,
+            timestep: Time::<Fixed>::default().timestep().as_secs_f32(),
+        }
+    }
+}
+
+/// A character moving through a course.
+#[derive(Debug, Clone, Copy)]
+struct Body {
+    /// Bottom-left corner, from the bottom-left of the level
+    position: Vec2,
+    velocity: Vec2,
+}
+
+/// Where a simulated jump ended up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Landing {
+    Surface(usize),
+    /// Fell in a pit, ran into a spike, or was still in the air after [`MAX_AIR_TICKS`]
+    Lost,
+}
+
+impl JumpPhysics {
+    /// Horizontal speed of a character that's been running in one direction for a while.
+    pub fn top_speed(&self) -> f32 {
+        self.acceleration * self.timestep * self.damping / (1. - self.damping)
+    }
+
+    /// Highest and furthest a running jump held all the way up goes, in whole tiles.
+    pub fn reach(&self) -> (i32, i32) {
+        let mut body = Body {
+            position: Vec2::ZERO,
+            velocity: vec2(self.top_speed(), self.jump_impulse),
+        };
+        let mut height: f32 = 0.;
+        self.accelerate(&mut body, 1., false);
+        body.position += body.velocity * self.timestep;
+        while body.position.y > 0. {
+            height = height.max(body.position.y);
+            self.accelerate(&mut body, 1., true);
+            body.position += body.velocity * self.timestep;
+        }
+        (
+            (height / TILE_SIZE) as i32,
+            (body.position.x / TILE_SIZE) as i32,
+        )
+    }
+
+    /// Whether the goal of `level` can be reached from its start.
+    pub fn is_solvable(&self, level: &LevelAsset) -> bool {
+        let terrain = Terrain::new(level, self.size);
+        let Some(goal) = terrain.surface_at(level.goal.0, level.goal.1) else {
+            return false;
+        };
+        // The player is dropped on the start
+        let start = Body {
+            position: vec2(level.start.0 as f32, level.start.1 as f32) * TILE_SIZE,
+            velocity: Vec2::ZERO,
+        };
+        let Landing::Surface(start) = self.fly(&terrain, start, 0., false, 0) else {
+            return false;
+        };
+
+        let mut reached = vec![false; terrain.surfaces.len()];
+        reached[start] = true;
+        let mut to_visit = vec![start];
+        while let Some(surface) = to_visit.pop() {
+            if surface == goal {
+                return true;
+            }
+            for landing in self.jumps_from(&terrain, surface) {
+                if !reached[landing] {
+                    reached[landing] = true;
+                    to_visit.push(landing);
+                }
+            }
+        }
+        false
+    }
+
+    /// Surfaces a character can get to from `surface`, with a running jump or walking off its edges.
+    fn jumps_from(&self, terrain: &Terrain, surface: usize) -> Vec<usize> {
+        let Surface { y, from, to } = terrain.surfaces[surface];
+        let bottom = y as f32 * TILE_SIZE;
+        let mut landings = Vec::new();
+        let mut land = |landing| match landing {
+            Landing::Surface(landing) if landing != surface && !landings.contains(&landing) => {
+                landings.push(landing);
+            }
+            _ => {}
+        };
+
+        for direction in [-1., 1.] {
+            // Standing on each tile of the surface, on either side of it
+            let takeoffs = (from..=to).flat_map(|x| {
+                let left = x as f32 * TILE_SIZE;
+                [left, left + TILE_SIZE - self.size.x]
+            });
+            for x in takeoffs {
+                let position = vec2(x, bottom);
+                if terrain.hits_solid(position, self.size)
+                    || terrain.hits_spike(position, self.size)
+                {
+                    continue;
+                }
+                let body = Body {
+                    position,
+                    velocity: vec2(direction * self.top_speed(), self.jump_impulse),
+                };
+                for hold in HOLD_TICKS {
+                    // Steering all the way, or letting go of the direction once in the air
+                    land(self.fly(terrain, body, direction, true, hold));
+                    land(self.fly(terrain, body, 0., true, hold));
+                }
+            }
+
+            // Running off the edge without jumping
+            let edge = if direction > 0. {
+                (to + 1) as f32 * TILE_SIZE
+            } else {
+                from as f32 * TILE_SIZE - self.size.x
+            };
+            let body = Body {
+                position: vec2(edge, bottom),
+                velocity: vec2(direction * self.top_speed(), 0.),
+            };
+            if !terrain.hits_solid(body.position, self.size) {
+                land(self.fly(terrain, body, direction, false, 0));
+            }
+        }
+        landings
+    }
+
+    /// Simulate a character in the air until it lands, pressing `direction` and holding the jump
+    /// for `hold` ticks after jumping.
+    fn fly(
+        &self,
+        terrain: &Terrain,
+        mut body: Body,
+        direction: f32,
+        jumped: bool,
+        hold: u32,
+    ) -> Landing {
+        for tick in 0..MAX_AIR_TICKS {
+            self.accelerate(&mut body, direction, jumped && tick > 0 && tick <= hold);
+
+            // Like the physics engine, running into a wall stops the character but it keeps falling
+            let previous = body.position;
+            body.position.x += body.velocity.x * self.timestep;
+            if terrain.hits_solid(body.position, self.size) {
+                body.position.x = previous.x;
+                body.velocity.x = 0.;
+            }
+            body.position.y += body.velocity.y * self.timestep;
+            if terrain.hits_solid(body.position, self.size) {
+                if body.velocity.y > 0. {
+                    // Bumped into a ceiling
+                    let top = ((body.position.y + self.size.y) / TILE_SIZE).floor() * TILE_SIZE;
+                    body.position.y = top - self.size.y;
+                    body.velocity.y = 0.;
+                } else {
+                    let floor = ((body.position.y / TILE_SIZE).floor() + 1.) * TILE_SIZE;
+                    body.position.y = floor;
+                    if terrain.hits_solid(body.position, self.size)
+                        || terrain.hits_spike(body.position, self.size)
+                    {
+                        return Landing::Lost;
+                    }
+                    return terrain.landing(body.position, self.size);
+                }
+            }
+
+            if body.position.y < 0. || terrain.hits_spike(body.position, self.size) {
+                return Landing::Lost;
+            }
+        }
+        Landing::Lost
+    }
+
+    /// Apply one tick of movement to the velocity, in the same order as the game: input, damping,
+    /// then gravity.
+    fn accelerate(&self, body: &mut Body, direction: f32, holding_jump: bool) {
+        body.velocity.x += direction * self.acceleration * self.timestep;
+        if holding_jump && body.velocity.y > 0. {
+            body.velocity.y += self.jump_impulse * self.hold_boost;
+        }
+        body.velocity.x *= self.damping;
+        body.velocity.y -= self.gravity * self.timestep;
+    }
+}
+
+/// Tiles in a row that a character can stand on and walk along.
+#[derive(Debug, Clone, Copy)]
+struct Surface {
+    /// Row the character stands in
+    y: i32,
+    from: i32,
+    to: i32,
+}
+
+/// The tiles of a level, to tell where a character can stand and what it runs into.
+struct Terrain {
+    width: i32,
+    height: i32,
+    solid: Vec<bool>,
+    /// Hitboxes of the spikes
+    spikes: Vec<Rect>,
+    surfaces: Vec<Surface>,
+    /// Index of the surface each tile is part of
+    surface_map: Vec<Option<usize>>,
+}
+
+impl Terrain {
+    fn new(level: &LevelAsset, character_size: Vec2) -> Self {
+        let blocks = || level.ground.iter().chain(&level.platforms);
+        let width = level.length as i32;
+        let headroom = (character_size.y / TILE_SIZE).ceil() as i32;
+        let height = blocks()
+            .map(|block| block.y + block.height as i32)
+            .max()
+            .unwrap_or(0)
+            + headroom
+            + 1;
+
+        let mut terrain = Self {
+            width,
+            height,
+            solid: vec![false; (width * height) as usize],
+            spikes: Vec::new(),
+            surfaces: Vec::new(),
+            surface_map: vec![None; (width * height) as usize],
+        };
+        for block in blocks() {
+            for x in block.x..block.x + block.width as i32 {
+                for y in block.y..block.y + block.height as i32 {
+                    if let Some(index) = terrain.index(x, y) {
+                        terrain.solid[index] = true;
+                    }
+                }
+            }
+        }
+        // The same hitbox as the spike's collider, centered on its tile
+        let mut spike_tiles = Vec::new();
+        for obstacle in &level.obstacles {
+            if obstacle.kind == ObstacleType::Spike {
+                let (x, y) = obstacle.position;
+                let bottom_center = vec2((x as f32 + 0.5) * TILE_SIZE, y as f32 * TILE_SIZE);
+                terrain.spikes.push(Rect::from_corners(
+                    bottom_center + vec2(-14., 0.),
+                    bottom_center + vec2(14., 28.),
+                ));
+                spike_tiles.push((x, y));
+            }
+        }
+
+        for y in 1..height {
+            let mut x = 0;
+            while x < width {
+                let standable = |x: i32| {
+                    terrain.is_solid(x, y - 1)
+                        && (y..y + headroom).all(|y| !terrain.is_solid(x, y))
+                        && !spike_tiles.contains(&(x, y))
+                };
+                if !standable(x) {
+                    x += 1;
+                    continue;
+                }
+                let from = x;
+                while x + 1 < width && standable(x + 1) {
+                    x += 1;
+                }
+                let surface = terrain.surfaces.len();
+                terrain.surfaces.push(Surface { y, from, to: x });
+                for x in from..=x {
+                    if let Some(index) = terrain.index(x, y) {
+                        terrain.surface_map[index] = Some(surface);
+                    }
+                }
+                x += 1;
+            }
+        }
+        terrain
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        ((0..self.width).contains(&x) && (0..self.height).contains(&y))
+            .then_some((y * self.width + x) as usize)
+    }
+
+    /// Walls close both ends of the level, and there's nothing under it or above the blocks.
+    fn is_solid(&self, x: i32, y: i32) -> bool {
+        if !(0..self.width).contains(&x) {
+            return true;
+        }
+        self.index(x, y).is_some_and(|index| self.solid[index])
+    }
+
+    fn surface_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.index(x, y).and_then(|index| self.surface_map[index])
+    }
+
+    /// Tiles a box at `position` overlaps, from its bottom-left one to its top-right one. Touching
+    /// a tile doesn't count.
+    fn tiles_under(position: Vec2, size: Vec2) -> (IVec2, IVec2) {
+        let min = ((position + TOUCHING) / TILE_SIZE).floor().as_ivec2();
+        let max = ((position + size - TOUCHING) / TILE_SIZE).ceil().as_ivec2() - IVec2::ONE;
+        (min, max)
+    }
+
+    fn hits_solid(&self, position: Vec2, size: Vec2) -> bool {
+        let (min, max) = Self::tiles_under(position, size);
+        (min.x..=max.x).any(|x| (min.y..=max.y).any(|y| self.is_solid(x, y)))
+    }
+
+    fn hits_spike(&self, position: Vec2, size: Vec2) -> bool {
+        let body = Rect::from_corners(position, position + size);
+        self.spikes
+            .iter()
+            .any(|spike| !spike.intersect(body).is_empty())
+    }
+
+    /// The surface a box standing at `position` is on.
+    fn landing(&self, position: Vec2, size: Vec2) -> Landing {
+        let (min, max) = Self::tiles_under(position, size);
+        (min.x..=max.x)
+            .find_map(|x| self.surface_at(x, min.y))
+            .map_or(Landing::Lost, Landing::Surface)
+    }
+}
+
+/// How big features get at a difficulty, within what the player can jump.
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    /// Widest gap, in tiles
+    gap: i32,
+    /// Highest the ground or a platform goes up or down from one feature to the next, in tiles
+    rise: i32,
+    /// Highest step of a staircase, in tiles
+    step: i32,
+    /// Free tiles under a ceiling
+    clearance: i32,
+    /// Narrowest platform, in tiles
+    platform_width: i32,
+    /// Most spikes in a row
+    spikes: i32,
+}
+
+/// Lays out courses for a difficulty, from 0 (a walk in the park) to 1 (the longest jumps the
+/// player can make).
+#[derive(Debug, Clone, Copy)]
+pub struct LevelGenerator {
+    /// Tiles after which no new feature starts
+    pub length: u32,
+    pub difficulty: f32,
+    pub physics: JumpPhysics,
+}
+
+/// A course being laid out from left to right.
+struct Layout {
+    level: LevelAsset,
+    /// Where the next feature starts
+    x: i32,
+    /// Height of the ground, in tiles
+    height: i32,
+}
+
+impl Layout {
+    fn ground(&mut self, width: i32) {
+        self.level.ground.push(TileRect {
+            x: self.x,
+            y: 0,
+            width: width as u32,
+            height: self.height as u32,
+        });
+        self.x += width;
+    }
+
+    fn gap(&mut self, width: i32) {
+        self.x += width;
+    }
+
+    /// A platform floating at `top`, the row a character stands in on it.
+    fn platform(&mut self, width: i32, top: i32) {
+        self.level.platforms.push(TileRect {
+            x: self.x,
+            y: top - 1,
+            width: width as u32,
+            height: 1,
+        });
+        self.x += width;
+    }
+
+    /// A ceiling above the ground that's coming next, leaving `clearance` free tiles under it.
+    fn ceiling(&mut self, offset: i32, width: i32, clearance: i32) {
+        self.level.platforms.push(TileRect {
+            x: self.x + offset,
+            y: self.height + clearance,
+            width: width as u32,
+            height: 2,
+        });
+    }
+
+    fn spike(&mut self, offset: i32) {
+        self.level.obstacles.push(PlacedObstacle {
+            kind: ObstacleType::Spike,
+            position: (self.x + offset, self.height),
+            patrol: None,
+        });
+    }
+
+    /// Move the ground or a platform up or down by at most `rise` tiles.
+    fn shift(height: i32, rise: i32, rng: &mut GameRng) -> i32 {
+        (height + rng.random_range(-rise..=rise)).clamp(*HEIGHTS.start(), *HEIGHTS.end())
+    }
+}
+
+impl LevelGenerator {
+    pub fn new(length: u32, difficulty: f32) -> Self {
+        Self {
+            length,
+            difficulty: difficulty.clamp(0., 1.),
+            physics: JumpPhysics::default(),
+        }
+    }
+
+    /// A course the player can finish, always the same for a seed.
+    pub fn generate(&self, seed: u64) -> LevelAsset {
+        self.try_generate(seed).unwrap_or_else(|| {
+            warn!("No solvable course generated from seed {seed}, using flat ground instead");
+            let mut layout = self.start(seed);
+            layout.ground(self.length as i32 + END_RUN - layout.x);
+            self.finish(layout)
+        })
+    }
+
+    /// A course the player can finish, or `None` if none of the [`MAX_ATTEMPTS`] from the seed was.
+    pub fn try_generate(&self, seed: u64) -> Option<LevelAsset> {
+        let mut rng = GameRng::new(seed);
+        for attempt in 1..=MAX_ATTEMPTS {
+            let level = self.layout(seed, &mut rng);
+            if self.physics.is_solvable(&level) {
+                info!("Generated a solvable course from seed {seed} after {attempt} attempts");
+                return Some(level);
+            }
+        }
+        None
+    }
+
+    fn limits(&self) -> Limits {
+        let (height, distance) = self.physics.reach();
+        let scale = |easiest: i32, hardest: i32| {
+            easiest + ((hardest - easiest) as f32 * self.difficulty).round() as i32
+        };
+        Limits {
+            gap: scale(2, distance - REACH_MARGIN),
+            rise: scale(1, (height - REACH_MARGIN) / 2),
+            step: scale(1, 3),
+            clearance: scale(6, 3),
+            platform_width: scale(5, 2),
+            spikes: scale(1, 4),
+        }
+    }
+
+    fn start(&self, seed: u64) -> Layout {
+        Layout {
+            level: LevelAsset {
+                name: format!("Generated {seed}"),
+                length: 0,
+                start: (1, START_HEIGHT),
+                goal: (0, 0),
+                ground: Vec::new(),
+                platforms: Vec::new(),
+                obstacles: Vec::new(),
+            },
+            x: 0,
+            height: START_HEIGHT,
+        }
+    }
+
+    fn finish(&self, mut layout: Layout) -> LevelAsset {
+        let length = layout.x;
+        layout.level.length = length as u32;
+        layout.level.goal = (length - GOAL_FROM_END, layout.height);
+        layout.level
+    }
+
+    fn layout(&self, seed: u64, rng: &mut GameRng) -> LevelAsset {
+        let limits = self.limits();
+        let mut layout = self.start(seed);
+        layout.ground(START_RUN);
+
+        while layout.x < self.length as i32 {
+            match rng.random_range(0..5) {
+                // A gap, landing higher or lower
+                0 => {
+                    layout.gap(rng.random_range(1..=limits.gap));
+                    layout.height = Layout::shift(layout.height, limits.rise, rng);
+                    layout.ground(rng.random_range(3..=6));
+                }
+                // Stairs going up or down
+                1 => {
+                    let direction = if rng.random_range(0..2) == 0 { 1 } else { -1 };
+                    for _ in 0..rng.random_range(2..=4) {
+                        layout.height = (layout.height
+                            + direction * rng.random_range(1..=limits.step))
+                        .clamp(*HEIGHTS.start(), *HEIGHTS.end());
+                        layout.ground(rng.random_range(2..=3));
+                    }
+                    layout.ground(2);
+                }
+                // Platforms floating over a pit
+                2 => {
+                    let mut top = layout.height;
+                    for _ in 0..rng.random_range(1..=2) {
+                        layout.gap(rng.random_range(1..=limits.gap));
+                        top = Layout::shift(top, limits.rise, rng);
+                        layout.platform(rng.random_range(limits.platform_width..=5), top);
+                    }
+                    layout.gap(rng.random_range(1..=limits.gap));
+                    layout.height = Layout::shift(top, limits.rise, rng);
+                    layout.ground(rng.random_range(3..=6));
+                }
+                // A ceiling to get under, maybe with a spike to hop over, or a gap when it's too low
+                3 => {
+                    let width = rng.random_range(4..=8);
+                    layout.ceiling(1, width - 2, limits.clearance);
+                    if rng.random_range(0.0..1.0) >= self.difficulty {
+                        layout.ground(width);
+                    } else if limits.clearance >= MIN_SPIKE_CLEARANCE {
+                        layout.spike(width / 2);
+                        layout.ground(width);
+                    } else {
+                        layout.ground(width / 2);
+                        layout.gap(1);
+                        layout.ground(width - width / 2 - 1);
+                    }
+                }
+                // Spikes on flat ground
+                _ => {
+                    layout.ground(rng.random_range(3..=5));
+                    let count = rng.random_range(1..=limits.spikes);
+                    for offset in 0..count {
+                        layout.spike(offset);
+                    }
+                    layout.ground(count + rng.random_range(3..=5));
+                }
+            }
+        }
+
+        layout.ground(END_RUN);
+        self.finish(layout)
+    }
+}
+
+impl GeneratorPlugin {
+    /// Play a course generated from the seed, or the level file, as the settings say.
+    fn pick_course(
+        mut commands: Commands,
+        settings: Res<Settings>,
+        daily: Res<DailyChallenge>,
+        campaign: Res<CampaignRun>,
+        rng: Res<GameRng>,
+        level_id: Res<LevelId>,
+        asset_server: Res<AssetServer>,
+        mut levels: ResMut<Assets<LevelAsset>>,
+    ) {
+        // The daily challenge and the campaign bring their own level
+        if daily.is_active() || campaign.0.is_some() {
+            return;
+        }
+        let Some(difficulty) = settings.course.difficulty() else {
+            // Back to the level file after playing generated courses
+            if level_id.0.starts_with(GENERATED_LEVEL_ID) {
+                commands.insert_resource(CurrentLevel(asset_server.load(DEFAULT_LEVEL_PATH)));
+                commands.insert_resource(LevelId(DEFAULT_LEVEL_PATH.to_string()));
+            }
+            return;
+        };
+
+        let seed = rng.seed();
+        let level = LevelGenerator::new(COURSE_LENGTH, difficulty).generate(seed);
+        commands.insert_resource(LevelId(format!(
+            "{GENERATED_LEVEL_ID}-{seed}-{:?}",
+            settings.course
+        )));
+        commands.insert_resource(CurrentLevel(levels.add(level)));
+    }
+}
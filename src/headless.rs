@@ -0,0 +1,232 @@
+//! Run the game without a window, rendering or audio, to simulate it in tests.
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use gmtk::headless::{HeadlessAppBuilder, Simulation};
+//!
+//! let mut app = HeadlessAppBuilder::default().build();
+//! app.start_run();
+//! app.hold(KeyCode::ArrowRight);
+//! app.tick_until(600, |outcomes| outcomes.goals_reached > 0);
+//! ```
+
+use bevy::{
+    asset::AssetPlugin,
+    gizmos::GizmoPlugin,
+    input::{
+        ButtonState, InputPlugin,
+        keyboard::{Key, KeyboardInput, NativeKey},
+        mouse::MouseButtonInput,
+    },
+    prelude::*,
+    state::app::StatesPlugin,
+    text::Font,
+    time::TimeUpdateStrategy,
+};
+
+use crate::{
+    GameState, LevelDimensions, LevelId, SimulationPlugin,
+    audio::PlaySfx,
+    daily::DailyChallenge,
+    environment::EnvironmentPlugin,
+    input::InputMap,
+    level::{CurrentLevel, LevelAsset},
+    modes::{GoalReached, ModesManagement},
+    obstacles::ObstaclePlugin,
+    player::{Player, PlayerDeath, PlayerPlugin},
+    rng::{GameRng, RngPlugin},
+    settings::Settings,
+};
+
+/// Level played if no other is given, the same one as the game starts with.
+const DEFAULT_LEVEL: &str = include_str!("../assets/levels/default.level.ron");
+
+/// Builds an [`App`] with the gameplay plugins only.
+/// Every [`App::update`] advances the game by exactly one fixed timestep.
+pub struct HeadlessAppBuilder {
+    level: LevelAsset,
+    seed: u64,
+}
+
+impl Default for HeadlessAppBuilder {
+    fn default() -> Self {
+        Self {
+            level: ron::from_str(DEFAULT_LEVEL).expect("the default level should be valid"),
+            seed: 0,
+        }
+    }
+}
+
+impl HeadlessAppBuilder {
+    pub fn with_level(mut self, level: LevelAsset) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn build(self) -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            InputPlugin,
+            StatesPlugin,
+            GizmoPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .init_asset::<Image>()
+        .init_asset::<Font>()
+        .init_asset::<LevelAsset>()
+        // Usually added by the window plugin, and read when placing obstacles
+        .add_event::<CursorMoved>()
+        // Sounds are requested by gameplay, but never played
+        .add_event::<PlaySfx>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(
+            Time::<Fixed>::default().timestep(),
+        ))
+        .add_plugins(SimulationPlugin)
+        // Skip the splash screen
+        .insert_state(GameState::Menu)
+        .insert_resource(LevelDimensions::new(self.level.length))
+        .insert_resource(LevelId(self.level.name.clone()))
+        // Not the saved ones, so tests don't depend on who runs them
+        .init_resource::<InputMap>()
+        .init_resource::<Settings>()
+        .init_resource::<DailyChallenge>()
+        .insert_resource(GameRng::new(self.seed))
+        .add_plugins((
+            PlayerPlugin,
+            EnvironmentPlugin,
+            ObstaclePlugin,
+            ModesManagement,
+            RngPlugin,
+        ))
+        .init_resource::<Outcomes>()
+        .add_systems(FixedPreUpdate, count_outcomes.before(crate::update_state));
+
+        let level = app
+            .world_mut()
+            .resource_mut::<Assets<LevelAsset>>()
+            .add(self.level);
+        app.insert_resource(CurrentLevel(level));
+        app
+    }
+}
+
+/// What happened during the simulation so far.
+#[derive(Debug, Resource, Default, Clone, PartialEq, Eq)]
+pub struct Outcomes {
+    pub goals_reached: u32,
+    pub deaths: u32,
+}
+
+fn count_outcomes(
+    mut outcomes: ResMut<Outcomes>,
+    mut goal_reader: EventReader<GoalReached>,
+    mut death_reader: EventReader<PlayerDeath>,
+) {
+    outcomes.goals_reached += goal_reader.read().count() as u32;
+    outcomes.deaths += death_reader.read().count() as u32;
+}
+
+/// Drive a headless [`App`] like a player would.
+pub trait Simulation {
+    /// Go from the menu to the game, spawning the level and the player.
+    fn start_run(&mut self);
+    /// Advance the game by `ticks` fixed timesteps.
+    fn tick(&mut self, ticks: u32);
+    /// Advance the game until `done` returns true, for at most `max_ticks` fixed timesteps.
+    /// Returns whether `done` returned true.
+    fn tick_until(&mut self, max_ticks: u32, done: impl FnMut(&Outcomes) -> bool) -> bool;
+    fn hold(&mut self, key: KeyCode);
+    fn release(&mut self, key: KeyCode);
+    /// Press and release `key` over two ticks, so it's seen as just pressed. The input plugin
+    /// forgets what was just pressed at the start of every tick, so this goes through its events.
+    fn tap(&mut self, key: KeyCode);
+    /// Press and release the left mouse button over two ticks, like [`Simulation::tap`].
+    fn click(&mut self);
+    fn outcomes(&self) -> &Outcomes;
+    fn player_position(&mut self) -> Option<Vec2>;
+}
+
+impl Simulation for App {
+    fn start_run(&mut self) {
+        // Finish the startup schedules before changing state
+        self.update();
+        self.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Game);
+        self.update();
+    }
+
+    fn tick(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.update();
+        }
+    }
+
+    fn tick_until(&mut self, max_ticks: u32, mut done: impl FnMut(&Outcomes) -> bool) -> bool {
+        for _ in 0..max_ticks {
+            self.update();
+            if done(self.outcomes()) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn hold(&mut self, key: KeyCode) {
+        self.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+    }
+
+    fn release(&mut self, key: KeyCode) {
+        self.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(key);
+    }
+
+    fn tap(&mut self, key: KeyCode) {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            self.world_mut().send_event(KeyboardInput {
+                key_code: key,
+                logical_key: Key::Unidentified(NativeKey::Unidentified),
+                state,
+                text: None,
+                repeat: false,
+                window: Entity::PLACEHOLDER,
+            });
+            self.update();
+        }
+    }
+
+    fn click(&mut self) {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            self.world_mut().send_event(MouseButtonInput {
+                button: MouseButton::Left,
+                state,
+                window: Entity::PLACEHOLDER,
+            });
+            self.update();
+        }
+    }
+
+    fn outcomes(&self) -> &Outcomes {
+        self.world().resource::<Outcomes>()
+    }
+
+    fn player_position(&mut self) -> Option<Vec2> {
+        self.world_mut()
+            .query_filtered::<&Transform, With<Player>>()
+            .single(self.world())
+            .ok()
+            .map(|transform| transform.translation.truncate())
+    }
+}
@@ -0,0 +1,249 @@
+//! Controls of the game.
+//!
+//! Keys are bound to [`Action`]s in the [`InputMap`], which players can change from the menu, and
+//! which is saved with the rest of the game's files.
+//! Gamepads have fixed bindings, and any connected gamepad can be used.
+
+use std::collections::HashMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+/// Sticks are ignored below this, so they don't drift.
+const STICK_DEAD_ZONE: f32 = 0.2;
+/// Where the input map is saved, inside the data directory.
+const INPUT_MAP_FILE: &str = "controls.ron";
+
+/// Everything the player can do with the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Jump,
+    CameraLeft,
+    CameraRight,
+    CameraUp,
+    CameraDown,
+    Pause,
+    /// Start the replay once the obstacle is placed
+    Continue,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Jump,
+        Action::CameraLeft,
+        Action::CameraRight,
+        Action::CameraUp,
+        Action::CameraDown,
+        Action::Pause,
+        Action::Continue,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Jump => "Jump",
+            Action::CameraLeft => "Camera left",
+            Action::CameraRight => "Camera right",
+            Action::CameraUp => "Camera up",
+            Action::CameraDown => "Camera down",
+            Action::Pause => "Pause",
+            Action::Continue => "Continue",
+        }
+    }
+
+    /// Whether the two actions can be triggered at the same time, so they can't share a key.
+    /// Moving the player and placing obstacles happen in different modes, but pausing is always
+    /// possible.
+    pub fn conflicts_with(&self, other: Action) -> bool {
+        let moves_player =
+            |action: Action| matches!(action, Action::MoveLeft | Action::MoveRight | Action::Jump);
+        *self == Action::Pause
+            || other == Action::Pause
+            || moves_player(*self) == moves_player(other)
+    }
+}
+
+/// Which keys trigger each [`Action`].
+#[derive(Debug, Resource, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    bindings: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            bindings: HashMap::from([
+                (Action::MoveLeft, vec![KeyCode::KeyA, KeyCode::ArrowLeft]),
+                (Action::MoveRight, vec![KeyCode::KeyD, KeyCode::ArrowRight]),
+                (Action::Jump, vec![KeyCode::Space]),
+                (Action::CameraLeft, vec![KeyCode::KeyA, KeyCode::ArrowLeft]),
+                (
+                    Action::CameraRight,
+                    vec![KeyCode::KeyD, KeyCode::ArrowRight],
+                ),
+                (Action::CameraUp, vec![KeyCode::KeyW, KeyCode::ArrowUp]),
+                (Action::CameraDown, vec![KeyCode::KeyS, KeyCode::ArrowDown]),
+                (Action::Pause, vec![KeyCode::Escape]),
+                (Action::Continue, vec![KeyCode::Space]),
+            ]),
+        }
+    }
+}
+
+impl InputMap {
+    /// Load the saved input map, with the default keys for actions that aren't in it.
+    pub fn load() -> Self {
+        let mut input_map: Self = storage::load_ron(storage::data_path(INPUT_MAP_FILE))
+            .unwrap_or_else(|err| {
+                info!("Using the default controls: {err}");
+                Self::default()
+            });
+        for (action, keys) in Self::default().bindings {
+            input_map.bindings.entry(action).or_insert(keys);
+        }
+        input_map
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = storage::save_ron(storage::data_path(INPUT_MAP_FILE), self) {
+            error!("Failed to save the controls: {err}");
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Make `key` the main key of `action`, keeping its other keys. An action it conflicts with
+    /// that was bound to `key` gets the replaced key instead.
+    pub fn rebind(&mut self, action: Action, key: KeyCode) {
+        let keys = self.bindings.entry(action).or_default();
+        let replaced = (!keys.is_empty()).then(|| keys.remove(0));
+        keys.retain(|bound| *bound != key);
+        keys.insert(0, key);
+
+        for (other, other_keys) in &mut self.bindings {
+            if *other == action || !action.conflicts_with(*other) {
+                continue;
+            }
+            let Some(index) = other_keys.iter().position(|bound| *bound == key) else {
+                continue;
+            };
+            match replaced.filter(|replaced| *replaced != key && !other_keys.contains(replaced)) {
+                Some(replaced) => {
+                    warn!("{key:?} was bound to {other:?} too, it gets {replaced:?} instead");
+                    other_keys[index] = replaced;
+                }
+                None => {
+                    warn!("{key:?} was bound to {other:?} too, it's unbound from it");
+                    other_keys.remove(index);
+                }
+            }
+        }
+    }
+
+    /// The keys of `action`, to show to the player.
+    pub fn describe(&self, action: Action) -> String {
+        let keys = self.keys(action);
+        if keys.is_empty() {
+            return "unbound".to_string();
+        }
+        keys.iter()
+            .map(|key| key_name(*key))
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
+
+/// Short name of a key, e.g. `A` instead of `KeyA`.
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    name.strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+/// Read the keyboard through the [`InputMap`].
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    input_map: Res<'w, InputMap>,
+}
+
+impl ActionInput<'_> {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.keyboard
+            .any_pressed(self.input_map.keys(action).iter().copied())
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.keyboard
+            .any_just_pressed(self.input_map.keys(action).iter().copied())
+    }
+}
+
+/// Run condition: a key bound to `action` is held.
+pub fn action_pressed(action: Action) -> impl FnMut(ActionInput) -> bool + Clone {
+    move |input: ActionInput| input.pressed(action)
+}
+
+/// Run condition: a key bound to `action` was just pressed.
+pub fn action_just_pressed(action: Action) -> impl FnMut(ActionInput) -> bool + Clone {
+    move |input: ActionInput| input.just_pressed(action)
+}
+
+/// Run condition: `button` was just pressed on any gamepad.
+pub fn gamepad_just_pressed(button: GamepadButton) -> impl FnMut(Query<&Gamepad>) -> bool + Clone {
+    move |gamepads: Query<&Gamepad>| gamepads.iter().any(|gamepad| gamepad.just_pressed(button))
+}
+
+/// Run condition: `button` is held on any gamepad.
+pub fn gamepad_pressed(button: GamepadButton) -> impl FnMut(Query<&Gamepad>) -> bool + Clone {
+    move |gamepads: Query<&Gamepad>| gamepads.iter().any(|gamepad| gamepad.pressed(button))
+}
+
+/// Where the left stick or the d-pad of any gamepad points to, each axis between -1 and 1.
+pub fn gamepad_direction(gamepads: &Query<&Gamepad>) -> Vec2 {
+    gamepads
+        .iter()
+        .map(|gamepad| {
+            let dpad = gamepad.dpad();
+            if dpad != Vec2::ZERO {
+                dpad
+            } else {
+                dead_zone(gamepad.left_stick())
+            }
+        })
+        .find(|direction| *direction != Vec2::ZERO)
+        .unwrap_or(Vec2::ZERO)
+}
+
+/// Where the right stick of any gamepad points to, each axis between -1 and 1.
+pub fn gamepad_right_stick(gamepads: &Query<&Gamepad>) -> Vec2 {
+    gamepads
+        .iter()
+        .map(|gamepad| dead_zone(gamepad.right_stick()))
+        .find(|direction| *direction != Vec2::ZERO)
+        .unwrap_or(Vec2::ZERO)
+}
+
+/// Whether `button` is held on any gamepad.
+pub fn any_gamepad_pressed(gamepads: &Query<&Gamepad>, button: GamepadButton) -> bool {
+    gamepads.iter().any(|gamepad| gamepad.pressed(button))
+}
+
+fn dead_zone(stick: Vec2) -> Vec2 {
+    if stick.length() < STICK_DEAD_ZONE {
+        Vec2::ZERO
+    } else {
+        stick.clamp_length_max(1.)
+    }
+}
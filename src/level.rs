@@ -0,0 +1,128 @@
+//! Levels are described in RON files under `assets/levels`, and loaded as assets.
+//! With the `hot_reload` feature, editing the file while the game is running respawns the level.
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{LevelId, obstacles::ObstacleType, storage::StorageError};
+
+/// Level loaded when the game starts.
+pub const DEFAULT_LEVEL_PATH: &str = "levels/default.level.ron";
+/// Where the player starts if the level isn't loaded yet.
+pub const DEFAULT_PLAYER_START: (i32, i32) = (1, 3);
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LevelAsset>()
+            .init_asset_loader::<LevelLoader>()
+            .add_systems(Startup, Self::load_default_level);
+    }
+}
+
+/// A rectangle of tiles, anchored on its bottom-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// An obstacle that's already there when the level starts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlacedObstacle {
+    pub kind: ObstacleType,
+    /// Grid position of the tile the obstacle sits on top of
+    pub position: (i32, i32),
+    /// Where a saw goes back and forth to, it stays in place without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patrol: Option<PlacedPatrol>,
+}
+
+/// The other end of a placed saw's path, and how fast it travels along it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlacedPatrol {
+    /// Grid position of the tile the saw turns around on top of
+    pub end: (i32, i32),
+    /// In world units per tick
+    pub speed: f32,
+}
+
+#[derive(Asset, TypePath, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelAsset {
+    pub name: String,
+    /// Length of the level, in tiles
+    pub length: u32,
+    /// Grid position the player starts at
+    pub start: (i32, i32),
+    /// Grid position of the goal's bottom-left corner
+    pub goal: (i32, i32),
+    #[serde(default)]
+    pub ground: Vec<TileRect>,
+    #[serde(default)]
+    pub platforms: Vec<TileRect>,
+    #[serde(default)]
+    pub obstacles: Vec<PlacedObstacle>,
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = LevelAsset;
+    type Settings = ();
+    type Error = StorageError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        ron::de::from_bytes(&bytes).map_err(StorageError::Deserialize)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+/// The level currently being played.
+#[derive(Debug, Resource)]
+pub struct CurrentLevel(pub Handle<LevelAsset>);
+
+/// Access to the data of the [`CurrentLevel`], if it's loaded.
+#[derive(SystemParam)]
+pub struct Level<'w> {
+    current: Res<'w, CurrentLevel>,
+    levels: Res<'w, Assets<LevelAsset>>,
+}
+
+impl Level<'_> {
+    pub fn get(&self) -> Option<&LevelAsset> {
+        self.levels.get(&self.current.0)
+    }
+
+    pub fn id(&self) -> AssetId<LevelAsset> {
+        self.current.0.id()
+    }
+
+    pub fn player_start(&self) -> (i32, i32) {
+        self.get().map_or(DEFAULT_PLAYER_START, |level| level.start)
+    }
+}
+
+impl LevelPlugin {
+    fn load_default_level(mut commands: Commands, asset_server: Res<AssetServer>) {
+        commands.insert_resource(CurrentLevel(asset_server.load(DEFAULT_LEVEL_PATH)));
+        commands.insert_resource(LevelId(DEFAULT_LEVEL_PATH.to_string()));
+    }
+}
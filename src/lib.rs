@@ -1,16 +1,28 @@
-use bevy::{
-    prelude::*,
-    window::{PrimaryWindow, WindowResized},
-};
+use avian2d::prelude::*;
+use bevy::prelude::*;
 
 use crate::modes::GameMode;
 
+pub mod audio;
 pub mod camera;
+pub mod campaign;
+pub mod daily;
+pub mod editor;
 pub mod environment;
+pub mod generator;
+pub mod headless;
+pub mod input;
+pub mod level;
 pub mod menu;
 pub mod modes;
 pub mod obstacles;
 pub mod player;
+pub mod rng;
+pub mod score;
+pub mod settings;
+pub mod storage;
+pub mod timeline;
+pub mod versus;
 
 #[derive(States, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GameState {
@@ -19,21 +31,40 @@ pub enum GameState {
     Menu,
     Game,
     Paused,
+    Editor,
+    GameOver,
+    /// A versus match is over
+    MatchOver,
 }
 
 pub struct SetupPlugin;
 
 impl Plugin for SetupPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            PreStartup,
-            (Self::create_level_dimensions, Self::start_background_music),
-        )
-        .add_systems(Update, Self::update_level_dimensions)
-        .add_systems(FixedPreUpdate, update_state)
-        .init_state::<GameState>()
-        .init_state::<GameMode>()
-        .init_asset::<AudioSource>();
+        app.add_plugins(SimulationPlugin)
+            .insert_resource(input::InputMap::load())
+            .init_asset::<AudioSource>();
+    }
+}
+
+/// The part of the setup that doesn't need a window, shared by the game and the
+/// [headless](headless) simulation: physics, states and the fixed simulation tick.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(PhysicsPlugins::default().with_length_unit(20.))
+            .insert_resource(Gravity(Vec2::NEG_Y * GRAVITY))
+            .add_systems(FixedPreUpdate, update_state)
+            .init_resource::<SimulationTick>()
+            .add_systems(
+                FixedFirst,
+                advance_simulation_tick.run_if(in_state(GameState::Game)),
+            )
+            .init_resource::<LevelDimensions>()
+            .init_resource::<LevelId>()
+            .init_state::<GameState>()
+            .init_state::<GameMode>();
     }
 }
 
@@ -42,6 +73,26 @@ fn update_state(world: &mut World) {
     world.try_run_schedule(StateTransition).unwrap();
 }
 
+/// Fixed ticks simulated while playing. Unlike [`FrameCount`](bevy::diagnostic::FrameCount), it
+/// doesn't depend on the frame rate and stands still while the game is paused, so anything timed
+/// with it plays out the same way on every run.
+#[derive(Debug, Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationTick(pub u32);
+
+fn advance_simulation_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+/// Pull of gravity, in world units per second squared.
+pub const GRAVITY: f32 = 1000.;
+/// Size of a tile, in world units.
+pub const TILE_SIZE: f32 = 25.6;
+/// Area of the world that's always visible, in world units. The camera scales it to fit the window.
+pub const VIEW_SIZE: Vec2 = vec2(1920., 1080.);
+/// Length of a level, in tiles, until one is loaded.
+const DEFAULT_LEVEL_LENGTH: u32 = 75;
+
+/// Where the level is in the world. Everything is in world units, whatever the size of the window.
 #[derive(Debug, Resource)]
 pub struct LevelDimensions {
     start: Vec2,
@@ -50,37 +101,41 @@ pub struct LevelDimensions {
     level_length: u32,
 }
 
-impl SetupPlugin {
-    fn create_level_dimensions(
-        mut commands: Commands,
-        window: Single<&Window, With<PrimaryWindow>>,
-    ) {
-        let level_length = 75;
-        commands.insert_resource(LevelDimensions {
-            start: window.size().map(|dimension| -dimension / 2.),
-            tile_size: window.size().x / level_length as f32,
-            level_length: level_length,
-        });
-    }
+/// Identifies the level being played, so saved replays can be matched with it.
+#[derive(Debug, Resource, Clone, PartialEq, Eq)]
+pub struct LevelId(pub String);
 
-    fn update_level_dimensions(
-        mut resize_reader: EventReader<WindowResized>,
-        mut level_dimensions: ResMut<LevelDimensions>,
-    ) {
-        for new_size in resize_reader.read() {
-            level_dimensions.start = vec2(-new_size.width / 2., -new_size.height / 2.);
-            level_dimensions.tile_size = new_size.width / level_dimensions.level_length as f32
-        }
+impl Default for LevelId {
+    fn default() -> Self {
+        Self("default".to_string())
     }
-    fn start_background_music(mut asset_server: Res<AssetServer>, mut commands: Commands) {
-        commands.spawn((
-            AudioPlayer::new(asset_server.load("sounds/bg_music.wav")),
-            PlaybackSettings::LOOP.with_volume(bevy::audio::Volume::Linear(0.5)),
-        ));
+}
+
+impl Default for LevelDimensions {
+    fn default() -> Self {
+        Self::new(DEFAULT_LEVEL_LENGTH)
     }
 }
 
 impl LevelDimensions {
+    /// Dimensions of a level of `level_length` tiles, starting at the bottom-left of the view.
+    pub fn new(level_length: u32) -> Self {
+        Self {
+            start: -VIEW_SIZE / 2.,
+            tile_size: TILE_SIZE,
+            level_length,
+        }
+    }
+
+    pub fn set_level_length(&mut self, level_length: u32) {
+        self.level_length = level_length;
+    }
+
+    /// Width of the whole level, in world units.
+    pub fn width(&self) -> f32 {
+        self.tile_size * self.level_length as f32
+    }
+
     /// Uses top-left anchor (because the physics engine doesn't work with anchors, we need to do this manually).
     /// If you want a center anchor, just pass Vec2::ZERO as the object_size.
     pub fn grid_pos_to_pixels(&self, pos: (i32, i32), object_size: Vec2) -> Vec2 {
@@ -90,4 +145,10 @@ impl LevelDimensions {
                 pos.1 as f32 * self.tile_size + object_size.y / 2.,
             )
     }
+
+    /// The tile a point is in.
+    pub fn pixels_to_grid_pos(&self, pos: Vec2) -> (i32, i32) {
+        let tile = ((pos - self.start) / self.tile_size).floor();
+        (tile.x as i32, tile.y as i32)
+    }
 }
@@ -1,33 +1,47 @@
-use avian2d::prelude::*;
-use bevy::{prelude::*, window::PresentMode};
+use bevy::prelude::*;
+use gmtk::{rng::GameRng, settings::Settings};
 
 fn main() {
+    let settings = Settings::load();
     App::new()
         .add_plugins((
             DefaultPlugins.set(WindowPlugin {
                 primary_window: Some(Window {
                     title: "Stop yourself".into(),
-                    present_mode: PresentMode::AutoNoVsync,
-                    mode: bevy::window::WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+                    present_mode: settings.present_mode(),
+                    mode: settings.window_mode(),
                     fit_canvas_to_parent: true,
                     ..Default::default()
                 }),
                 ..Default::default()
             }),
-            PhysicsPlugins::default().with_length_unit(20.),
             bevy_framepace::FramepacePlugin,
             //PhysicsDebugPlugin::default(),
         ))
         .add_plugins((
             gmtk::SetupPlugin,
+            gmtk::level::LevelPlugin,
             gmtk::player::PlayerPlugin,
             gmtk::environment::EnvironmentPlugin,
             gmtk::camera::CameraPlugin,
             gmtk::modes::ModesManagement,
             gmtk::obstacles::ObstaclePlugin,
             gmtk::menu::MenuPlugin,
+            gmtk::editor::EditorPlugin,
+            gmtk::score::ScorePlugin,
+            gmtk::settings::SettingsPlugin,
+            gmtk::audio::SoundPlugin,
+            gmtk::timeline::TimelinePlugin,
         ))
-        .insert_resource(Gravity(Vec2::NEG_Y * 1000.))
+        .add_plugins((
+            gmtk::rng::RngPlugin,
+            gmtk::daily::DailyPlugin,
+            gmtk::generator::GeneratorPlugin,
+            gmtk::versus::VersusPlugin,
+            gmtk::campaign::CampaignPlugin,
+        ))
+        .insert_resource(settings)
+        .insert_resource(GameRng::from_args().unwrap_or_default())
         .insert_resource(ClearColor(Color::BLACK))
         .run();
 }
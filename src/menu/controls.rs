@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+
+use crate::{
+    audio::{PlaySfx, SfxKind},
+    input::{Action, InputMap},
+    menu::{MenuButtonAction, MenuScreen},
+};
+
+/// Screen to change which keys are bound to each action.
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_systems(OnEnter(MenuScreen::Controls), Self::spawn_controls_screen)
+            .add_systems(
+                Update,
+                (
+                    Self::controls_action,
+                    Self::capture_key.run_if(|rebinding: Res<Rebinding>| rebinding.0.is_some()),
+                    Self::update_labels,
+                )
+                    .chain()
+                    .run_if(in_state(MenuScreen::Controls)),
+            )
+            .add_systems(
+                OnExit(MenuScreen::Controls),
+                (
+                    super::despawn_screen::<ControlsMarker>,
+                    |mut rebinding: ResMut<Rebinding>| rebinding.0 = None,
+                ),
+            );
+    }
+}
+
+#[derive(Component)]
+struct ControlsMarker;
+
+#[derive(Component, Debug)]
+enum ControlsButton {
+    Rebind(Action),
+    ResetToDefaults,
+}
+
+/// Text showing the keys bound to an action
+#[derive(Component)]
+struct BindingLabel(Action);
+
+/// The action waiting for a key press to be bound to it
+#[derive(Resource, Default)]
+struct Rebinding(Option<Action>);
+
+impl ControlsPlugin {
+    fn spawn_controls_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+        let title_font = asset_server.load(super::TITLE_FONT_PATH);
+        let button_text_font = TextFont {
+            font_size: 24.,
+            font: asset_server.load("fonts/capitolcity.ttf"),
+            ..Default::default()
+        };
+        let row_node = Node {
+            width: Val::Px(600.),
+            height: Val::Px(45.),
+            margin: UiRect::all(Val::Px(5.)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            border: UiRect::all(Val::Px(3.)),
+            ..Default::default()
+        };
+
+        commands
+            .spawn((
+                ControlsMarker,
+                Node {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                BackgroundColor(Color::srgb(0., 0., 0.)),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Text::new("Controls"),
+                    TextFont {
+                        font_size: 80.,
+                        font: title_font,
+                        ..Default::default()
+                    },
+                    TextColor(super::TEXT_COLOR),
+                    Node {
+                        margin: UiRect::all(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                ));
+                parent.spawn((
+                    Text::new("Click an action, then press the key to bind to it. [ESC] cancels."),
+                    button_text_font.clone(),
+                    TextColor(super::TEXT_COLOR),
+                    Node {
+                        margin: UiRect::bottom(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                ));
+                for action in Action::ALL {
+                    parent.spawn((
+                        Button,
+                        row_node.clone(),
+                        BackgroundColor(super::NORMAL_BUTTON),
+                        BorderColor(Color::BLACK),
+                        ControlsButton::Rebind(action),
+                        children![(
+                            Text::default(),
+                            BindingLabel(action),
+                            button_text_font.clone(),
+                            TextColor(super::TEXT_COLOR),
+                        )],
+                    ));
+                }
+                parent.spawn((
+                    Button,
+                    row_node.clone(),
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    ControlsButton::ResetToDefaults,
+                    children![(
+                        Text::new("Reset to defaults"),
+                        button_text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ));
+                parent.spawn((
+                    Button,
+                    row_node,
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::BackToMenu,
+                    children![(
+                        Text::new("Back"),
+                        button_text_font,
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ));
+            });
+    }
+
+    fn controls_action(
+        mut sfx_writer: EventWriter<PlaySfx>,
+        action: Query<
+            (&Interaction, &ControlsButton, &mut BackgroundColor),
+            (Changed<Interaction>, With<Button>),
+        >,
+        mut rebinding: ResMut<Rebinding>,
+        mut input_map: ResMut<InputMap>,
+    ) {
+        for (interaction, controls_action, mut background_color) in action {
+            if *interaction == Interaction::Pressed {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonSelect));
+                match controls_action {
+                    ControlsButton::Rebind(action) => {
+                        rebinding.0 = Some(*action);
+                    }
+                    ControlsButton::ResetToDefaults => {
+                        rebinding.0 = None;
+                        *input_map = InputMap::default();
+                        input_map.save();
+                    }
+                }
+            } else if *interaction == Interaction::Hovered {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonHover));
+            }
+
+            *background_color = match interaction {
+                Interaction::None => super::NORMAL_BUTTON.into(),
+                Interaction::Pressed => super::PRESSED_BUTTON.into(),
+                Interaction::Hovered => super::HOVERED_BUTTON.into(),
+            }
+        }
+    }
+
+    /// Bind the first key pressed to the action being rebound.
+    fn capture_key(
+        keyboard: Res<ButtonInput<KeyCode>>,
+        mut rebinding: ResMut<Rebinding>,
+        mut input_map: ResMut<InputMap>,
+    ) {
+        let (Some(action), Some(key)) = (rebinding.0, keyboard.get_just_pressed().next()) else {
+            return;
+        };
+        rebinding.0 = None;
+        if *key == KeyCode::Escape {
+            return;
+        }
+        info!("Binding {key:?} to {action:?}");
+        input_map.rebind(action, *key);
+        input_map.save();
+    }
+
+    fn update_labels(
+        input_map: Res<InputMap>,
+        rebinding: Res<Rebinding>,
+        labels: Query<(&mut Text, &BindingLabel)>,
+    ) {
+        for (mut text, BindingLabel(action)) in labels {
+            let keys = if rebinding.0 == Some(*action) {
+                "press a key...".to_string()
+            } else {
+                input_map.describe(*action)
+            };
+            text.0 = format!("{}: {keys}", action.label());
+        }
+    }
+}
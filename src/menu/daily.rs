@@ -0,0 +1,232 @@
+use bevy::prelude::*;
+
+use crate::{
+    audio::{PlaySfx, SfxKind},
+    daily::{
+        DailyResults, Date, ExportDailyResults, ImportDailyResults, StartDailyChallenge,
+        local_player,
+    },
+    menu::{MenuButtonAction, MenuScreen},
+};
+
+/// How many results of the day are shown.
+const LEADERBOARD_LENGTH: usize = 10;
+
+/// Screen to play today's challenge, and see how everyone did at it.
+pub struct DailyScreenPlugin;
+
+impl Plugin for DailyScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(MenuScreen::Daily), Self::spawn_daily_screen)
+            .add_systems(
+                Update,
+                (Self::daily_action, Self::update_labels)
+                    .chain()
+                    .run_if(in_state(MenuScreen::Daily)),
+            )
+            .add_systems(
+                OnExit(MenuScreen::Daily),
+                super::despawn_screen::<DailyMarker>,
+            );
+    }
+}
+
+#[derive(Component)]
+struct DailyMarker;
+
+#[derive(Component, Debug)]
+enum DailyButton {
+    Play,
+    Export,
+    Import,
+}
+
+#[derive(Component, Debug)]
+enum DailyLabel {
+    Play,
+    Leaderboard,
+}
+
+impl DailyScreenPlugin {
+    fn spawn_daily_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+        let title_font = asset_server.load(super::TITLE_FONT_PATH);
+        let text_font = TextFont {
+            font_size: 24.,
+            font: asset_server.load("fonts/capitolcity.ttf"),
+            ..Default::default()
+        };
+        let row_node = Node {
+            width: Val::Px(500.),
+            height: Val::Px(45.),
+            margin: UiRect::all(Val::Px(5.)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            border: UiRect::all(Val::Px(3.)),
+            ..Default::default()
+        };
+        commands.spawn((
+            DailyMarker,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            BackgroundColor(Color::srgb(0., 0., 0.)),
+            children![
+                (
+                    Text::new(format!("Daily challenge {}", Date::today())),
+                    TextFont {
+                        font_size: 60.,
+                        font: title_font,
+                        ..Default::default()
+                    },
+                    TextColor(super::TEXT_COLOR),
+                    Node {
+                        margin: UiRect::all(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    Text::new(
+                        "Everyone gets the same level and obstacles today. Only the first try counts!"
+                    ),
+                    text_font.clone(),
+                    TextColor(super::TEXT_COLOR),
+                ),
+                (
+                    Text::default(),
+                    text_font.clone(),
+                    TextColor(super::TEXT_COLOR),
+                    DailyLabel::Leaderboard,
+                    Node {
+                        margin: UiRect::all(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    Button,
+                    row_node.clone(),
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    DailyButton::Play,
+                    children![(
+                        Text::default(),
+                        text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                        DailyLabel::Play,
+                    )],
+                ),
+                (
+                    Button,
+                    row_node.clone(),
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    DailyButton::Export,
+                    children![(
+                        Text::new("Export my results"),
+                        text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
+                (
+                    Button,
+                    row_node.clone(),
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    DailyButton::Import,
+                    children![(
+                        Text::new("Import results"),
+                        text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
+                (
+                    Button,
+                    row_node,
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::BackToMenu,
+                    children![(
+                        Text::new("Back"),
+                        text_font,
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
+            ],
+        ));
+    }
+
+    fn daily_action(
+        mut sfx_writer: EventWriter<PlaySfx>,
+        action: Query<
+            (&Interaction, &DailyButton, &mut BackgroundColor),
+            (Changed<Interaction>, With<Button>),
+        >,
+        mut start_writer: EventWriter<StartDailyChallenge>,
+        mut export_writer: EventWriter<ExportDailyResults>,
+        mut import_writer: EventWriter<ImportDailyResults>,
+    ) {
+        for (interaction, daily_action, mut background_color) in action {
+            if *interaction == Interaction::Pressed {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonSelect));
+                match daily_action {
+                    DailyButton::Play => {
+                        start_writer.write(StartDailyChallenge);
+                    }
+                    DailyButton::Export => {
+                        export_writer.write(ExportDailyResults);
+                    }
+                    DailyButton::Import => {
+                        import_writer.write(ImportDailyResults);
+                    }
+                }
+            } else if *interaction == Interaction::Hovered {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonHover));
+            }
+
+            *background_color = match interaction {
+                Interaction::None => super::NORMAL_BUTTON.into(),
+                Interaction::Pressed => super::PRESSED_BUTTON.into(),
+                Interaction::Hovered => super::HOVERED_BUTTON.into(),
+            }
+        }
+    }
+
+    fn update_labels(results: Res<DailyResults>, labels: Query<(&mut Text, &DailyLabel)>) {
+        let today = Date::today();
+        for (mut text, label) in labels {
+            text.0 = match label {
+                DailyLabel::Play if results.played(today, &local_player()) => {
+                    "Already played today".to_string()
+                }
+                DailyLabel::Play => "Play".to_string(),
+                DailyLabel::Leaderboard => {
+                    let leaderboard = results.leaderboard(today);
+                    if leaderboard.is_empty() {
+                        "Nobody played today's challenge yet".to_string()
+                    } else {
+                        leaderboard
+                            .iter()
+                            .take(LEADERBOARD_LENGTH)
+                            .enumerate()
+                            .map(|(position, entry)| {
+                                format!(
+                                    "{:>2}. {:<12} {:>6} pts   {} loops   {:.0}s",
+                                    position + 1,
+                                    entry.player,
+                                    entry.points,
+                                    entry.loops,
+                                    entry.time_secs
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    }
+                }
+            };
+        }
+    }
+}
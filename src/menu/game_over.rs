@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+
+use crate::{
+    GameState,
+    audio::{PlaySfx, SfxKind},
+    menu::MenuButtonAction,
+    modes::RestartRun,
+    score::{HighScores, Score},
+};
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::GameOver), Self::spawn_game_over_screen)
+            .add_systems(
+                Update,
+                Self::game_over_action.run_if(in_state(GameState::GameOver)),
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                super::despawn_screen::<GameOverMarker>,
+            );
+    }
+}
+
+#[derive(Component)]
+struct GameOverMarker;
+
+impl GameOverPlugin {
+    fn spawn_game_over_screen(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        score: Res<Score>,
+        high_scores: Res<HighScores>,
+    ) {
+        let title_font = asset_server.load(super::TITLE_FONT_PATH);
+        let text_font = TextFont {
+            font_size: 24.,
+            font: asset_server.load("fonts/capitolcity.ttf"),
+            ..Default::default()
+        };
+
+        let button_node = Node {
+            width: Val::Px(300.),
+            height: Val::Px(70.),
+            margin: UiRect::all(Val::Px(20.)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            border: UiRect::all(Val::Px(3.)),
+            ..Default::default()
+        };
+        let button_text_font = TextFont {
+            font_size: 33.,
+            ..text_font.clone()
+        };
+
+        let high_score_table = high_scores
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(position, entry)| {
+                format!(
+                    "{:>2}. {:>6} pts   {} loops   {:.0}s",
+                    position + 1,
+                    entry.points,
+                    entry.loops,
+                    entry.time_secs
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        commands.spawn((
+            GameOverMarker,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            BackgroundColor(Color::srgba(0., 0., 0., 0.8)),
+            children![
+                (
+                    Text::new("Game over"),
+                    TextFont {
+                        font_size: 80.,
+                        font: title_font,
+                        ..Default::default()
+                    },
+                    TextColor(super::TEXT_COLOR),
+                    Node {
+                        margin: UiRect::all(Val::Px(30.)),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    Text::new(format!(
+                        "Score: {}   Loops: {}   Obstacles survived: {}",
+                        score.points(),
+                        score.loops,
+                        score.obstacles_survived
+                    )),
+                    text_font.clone(),
+                    TextColor(super::TEXT_COLOR),
+                ),
+                (
+                    Text::new(format!("High scores\n{high_score_table}")),
+                    text_font.clone(),
+                    TextColor(super::TEXT_COLOR),
+                    Node {
+                        margin: UiRect::all(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::RestartLevel,
+                    children![(
+                        Text::new("Try again"),
+                        button_text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::Exit,
+                    children![(
+                        Text::new("Exit"),
+                        button_text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                )
+            ],
+        ));
+    }
+
+    fn game_over_action(
+        mut sfx_writer: EventWriter<PlaySfx>,
+        action: Query<
+            (&Interaction, &MenuButtonAction, &mut BackgroundColor),
+            (Changed<Interaction>, With<Button>),
+        >,
+        mut app_exit_events: EventWriter<AppExit>,
+        mut restart_writer: EventWriter<RestartRun>,
+        mut app_state: ResMut<NextState<GameState>>,
+    ) {
+        for (interaction, menu_action, mut background_color) in action {
+            if *interaction == Interaction::Pressed {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonSelect));
+                match menu_action {
+                    MenuButtonAction::Exit => {
+                        app_exit_events.write(AppExit::Success);
+                    }
+                    MenuButtonAction::RestartLevel => {
+                        restart_writer.write(RestartRun);
+                        app_state.set(GameState::Game);
+                    }
+                    _ => {
+                        // Not on this screen
+                    }
+                }
+            } else if *interaction == Interaction::Hovered {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonHover));
+            }
+
+            *background_color = match interaction {
+                Interaction::None => super::NORMAL_BUTTON.into(),
+                Interaction::Pressed => super::PRESSED_BUTTON.into(),
+                Interaction::Hovered => super::HOVERED_BUTTON.into(),
+            }
+        }
+    }
+}
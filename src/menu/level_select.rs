@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+
+use crate::{
+    audio::{PlaySfx, SfxKind},
+    campaign::{CAMPAIGN, CampaignLevels, CampaignProgress, CampaignRun, StartCampaignLevel},
+    level::LevelAsset,
+    menu::{MenuButtonAction, MenuScreen},
+};
+
+/// Screen to pick a level of the campaign, showing which ones are unlocked and their best scores.
+pub struct LevelSelectScreenPlugin;
+
+impl Plugin for LevelSelectScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(MenuScreen::LevelSelect), Self::spawn_level_select)
+            .add_systems(OnEnter(MenuScreen::Main), Self::back_to_level_select)
+            .add_systems(
+                Update,
+                (Self::level_action, Self::update_labels)
+                    .chain()
+                    .run_if(in_state(MenuScreen::LevelSelect)),
+            )
+            .add_systems(
+                OnExit(MenuScreen::LevelSelect),
+                super::despawn_screen::<LevelSelectMarker>,
+            );
+    }
+}
+
+#[derive(Component)]
+struct LevelSelectMarker;
+
+/// Plays the level at this index in the campaign.
+#[derive(Component, Debug)]
+struct LevelButton(usize);
+
+/// Text of the [`LevelButton`] with the same index.
+#[derive(Component, Debug)]
+struct LevelLabel(usize);
+
+impl LevelSelectScreenPlugin {
+    fn spawn_level_select(mut commands: Commands, asset_server: Res<AssetServer>) {
+        let title_font = asset_server.load(super::TITLE_FONT_PATH);
+        let text_font = TextFont {
+            font_size: 24.,
+            font: asset_server.load("fonts/capitolcity.ttf"),
+            ..Default::default()
+        };
+        let row_node = Node {
+            width: Val::Px(500.),
+            height: Val::Px(45.),
+            margin: UiRect::all(Val::Px(5.)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            border: UiRect::all(Val::Px(3.)),
+            ..Default::default()
+        };
+        commands
+            .spawn((
+                LevelSelectMarker,
+                Node {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                BackgroundColor(Color::srgb(0., 0., 0.)),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Text::new("Campaign"),
+                    TextFont {
+                        font_size: 60.,
+                        font: title_font,
+                        ..Default::default()
+                    },
+                    TextColor(super::TEXT_COLOR),
+                    Node {
+                        margin: UiRect::all(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                ));
+                for index in 0..CAMPAIGN.len() {
+                    parent.spawn((
+                        Button,
+                        row_node.clone(),
+                        BackgroundColor(super::NORMAL_BUTTON),
+                        BorderColor(Color::BLACK),
+                        LevelButton(index),
+                        children![(
+                            Text::default(),
+                            text_font.clone(),
+                            TextColor(super::TEXT_COLOR),
+                            LevelLabel(index),
+                        )],
+                    ));
+                }
+                parent.spawn((
+                    Button,
+                    row_node,
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::BackToMenu,
+                    children![(Text::new("Back"), text_font, TextColor(super::TEXT_COLOR))],
+                ));
+            });
+    }
+
+    /// Coming back from a level of the campaign goes to the level select, to pick the next one.
+    fn back_to_level_select(
+        mut run: ResMut<CampaignRun>,
+        mut menu_screen: ResMut<NextState<MenuScreen>>,
+    ) {
+        if run.0.take().is_some() {
+            menu_screen.set(MenuScreen::LevelSelect);
+        }
+    }
+
+    fn level_action(
+        mut sfx_writer: EventWriter<PlaySfx>,
+        action: Query<
+            (&Interaction, &LevelButton, &mut BackgroundColor),
+            (Changed<Interaction>, With<Button>),
+        >,
+        mut start_writer: EventWriter<StartCampaignLevel>,
+    ) {
+        for (interaction, LevelButton(index), mut background_color) in action {
+            if *interaction == Interaction::Pressed {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonSelect));
+                start_writer.write(StartCampaignLevel(*index));
+            } else if *interaction == Interaction::Hovered {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonHover));
+            }
+
+            *background_color = match interaction {
+                Interaction::None => super::NORMAL_BUTTON.into(),
+                Interaction::Pressed => super::PRESSED_BUTTON.into(),
+                Interaction::Hovered => super::HOVERED_BUTTON.into(),
+            }
+        }
+    }
+
+    fn update_labels(
+        progress: Res<CampaignProgress>,
+        campaign_levels: Res<CampaignLevels>,
+        levels: Res<Assets<LevelAsset>>,
+        labels: Query<(&mut Text, &LevelLabel)>,
+    ) {
+        for (mut text, LevelLabel(index)) in labels {
+            let name = levels
+                .get(&campaign_levels.0[*index])
+                .map_or("...", |level| level.name.as_str());
+            text.0 = if !progress.is_unlocked(*index) {
+                format!("{}. Locked", index + 1)
+            } else if let Some(best) = progress.best_score(*index) {
+                format!("{}. {name}   best {best} pts", index + 1)
+            } else {
+                format!("{}. {name}", index + 1)
+            };
+        }
+    }
+}
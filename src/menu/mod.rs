@@ -1,8 +1,21 @@
-use bevy::prelude::*;
+use bevy::{
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+};
 
-use crate::GameState;
+use crate::{
+    GameState,
+    audio::{PlaySfx, SfxKind},
+    rng::GameRng,
+};
 
+mod controls;
+mod daily;
+mod game_over;
+mod level_select;
 mod pause;
+mod settings;
+mod versus;
 
 pub struct MenuPlugin;
 
@@ -11,31 +24,95 @@ struct SplashMarker;
 #[derive(Debug, Component)]
 struct MainMenuMarker;
 
+/// Text of the button picking a new seed
+#[derive(Debug, Component)]
+struct SeedLabel;
+
+/// Digits of the seed being typed in the main menu, if one is.
+#[derive(Debug, Resource, Default)]
+struct SeedEntry(Option<String>);
+
+/// Most digits a typed seed can have, so it fits in a `u64`
+const MAX_SEED_DIGITS: usize = 18;
+
 #[derive(Resource, Deref, DerefMut)]
 struct SplashTimer(Timer);
 
+/// Screens of the main menu
+#[derive(SubStates, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::Menu)]
+enum MenuScreen {
+    #[default]
+    Main,
+    Controls,
+    Daily,
+    Versus,
+    LevelSelect,
+}
+
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(pause::PausePlugin)
-            .add_systems(OnEnter(GameState::Splash), Self::splash_screen)
-            .add_systems(Update, Self::countdown.run_if(in_state(GameState::Splash)))
-            .add_systems(OnExit(GameState::Splash), despawn_screen::<SplashMarker>)
-            .add_systems(OnEnter(GameState::Menu), Self::main_menu)
-            .add_systems(Update, Self::menu_action.run_if(in_state(GameState::Menu)))
-            .add_systems(OnExit(GameState::Menu), despawn_screen::<MainMenuMarker>);
+        app.add_plugins((
+            pause::PausePlugin,
+            game_over::GameOverPlugin,
+            controls::ControlsPlugin,
+            daily::DailyScreenPlugin,
+            versus::VersusScreenPlugin,
+            level_select::LevelSelectScreenPlugin,
+            settings::SettingsScreenPlugin,
+        ))
+        .add_sub_state::<MenuScreen>()
+        .add_systems(OnEnter(GameState::Splash), Self::splash_screen)
+        .add_systems(Update, Self::countdown.run_if(in_state(GameState::Splash)))
+        .add_systems(OnExit(GameState::Splash), despawn_screen::<SplashMarker>)
+        .init_resource::<SeedEntry>()
+        .add_systems(OnEnter(MenuScreen::Main), Self::main_menu)
+        .add_systems(Update, Self::menu_action.run_if(in_state(GameState::Menu)))
+        .add_systems(
+            Update,
+            (
+                Self::type_seed.run_if(|entry: Res<SeedEntry>| entry.0.is_some()),
+                Self::update_seed_label
+                    .run_if(resource_changed::<GameRng>.or(resource_changed::<SeedEntry>)),
+            )
+                .chain()
+                .run_if(in_state(MenuScreen::Main)),
+        )
+        .add_systems(
+            OnExit(MenuScreen::Main),
+            (
+                despawn_screen::<MainMenuMarker>,
+                |mut entry: ResMut<SeedEntry>| entry.0 = None,
+            ),
+        );
     }
 }
 
 #[derive(Component, Debug)]
 pub enum MenuButtonAction {
     Play,
+    Campaign,
+    DailyChallenge,
+    Versus,
+    Editor,
+    Controls,
+    Settings,
+    NewSeed,
+    TypeSeed,
+    BackToMenu,
+    SaveReplay,
+    LoadReplay,
+    RestartLoop,
+    RestartLevel,
+    MainMenu,
+    Rematch,
     Exit,
 }
 
 const TITLE_FONT_PATH: &str = "fonts/title_font.ttf";
-const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
-const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
-const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
+pub(crate) const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+pub(crate) const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+pub(crate) const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
 const HOVERED_PRESSED_BUTTON: Color = Color::srgb(0.25, 0.65, 0.25);
 const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.75, 0.35);
 
@@ -79,13 +156,13 @@ impl MenuPlugin {
         commands.insert_resource(SplashTimer(Timer::from_seconds(1.0, TimerMode::Once)));
     }
 
-    fn main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    fn main_menu(mut commands: Commands, asset_server: Res<AssetServer>, rng: Res<GameRng>) {
         let title_font = asset_server.load(TITLE_FONT_PATH);
 
         let button_node = Node {
             width: Val::Px(300.),
-            height: Val::Px(70.),
-            margin: UiRect::all(Val::Px(20.)),
+            height: Val::Px(60.),
+            margin: UiRect::all(Val::Px(10.)),
             justify_content: JustifyContent::Center,
             align_items: AlignItems::Center,
             border: UiRect::all(Val::Px(3.)),
@@ -143,6 +220,103 @@ impl MenuPlugin {
                                 TextColor(TEXT_COLOR),
                             )],
                         ),
+                        (
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            BorderColor(Color::BLACK),
+                            MenuButtonAction::Campaign,
+                            children![(
+                                Text::new("Campaign"),
+                                button_text_font.clone(),
+                                TextColor(TEXT_COLOR),
+                            )],
+                        ),
+                        (
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            BorderColor(Color::BLACK),
+                            MenuButtonAction::DailyChallenge,
+                            children![(
+                                Text::new("Daily challenge"),
+                                button_text_font.clone(),
+                                TextColor(TEXT_COLOR),
+                            )],
+                        ),
+                        (
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            BorderColor(Color::BLACK),
+                            MenuButtonAction::Versus,
+                            children![(
+                                Text::new("Versus"),
+                                button_text_font.clone(),
+                                TextColor(TEXT_COLOR),
+                            )],
+                        ),
+                        (
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            BorderColor(Color::BLACK),
+                            MenuButtonAction::Editor,
+                            children![(
+                                Text::new("Level editor"),
+                                button_text_font.clone(),
+                                TextColor(TEXT_COLOR),
+                            )],
+                        ),
+                        (
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            BorderColor(Color::BLACK),
+                            MenuButtonAction::Controls,
+                            children![(
+                                Text::new("Controls"),
+                                button_text_font.clone(),
+                                TextColor(TEXT_COLOR),
+                            )],
+                        ),
+                        (
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            BorderColor(Color::BLACK),
+                            MenuButtonAction::Settings,
+                            children![(
+                                Text::new("Settings"),
+                                button_text_font.clone(),
+                                TextColor(TEXT_COLOR),
+                            )],
+                        ),
+                        (
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            BorderColor(Color::BLACK),
+                            MenuButtonAction::NewSeed,
+                            children![(
+                                SeedLabel,
+                                Text::new(seed_text(&rng, &SeedEntry::default())),
+                                button_text_font.clone(),
+                                TextColor(TEXT_COLOR),
+                            )],
+                        ),
+                        (
+                            Button,
+                            button_node.clone(),
+                            BackgroundColor(NORMAL_BUTTON),
+                            BorderColor(Color::BLACK),
+                            MenuButtonAction::TypeSeed,
+                            children![(
+                                Text::new("Type a seed"),
+                                button_text_font.clone(),
+                                TextColor(TEXT_COLOR),
+                            )],
+                        ),
                         (
                             Button,
                             button_node.clone(),
@@ -163,22 +337,20 @@ impl MenuPlugin {
     fn menu_action(
         mut commands: Commands,
         asset_server: Res<AssetServer>,
+        mut sfx_writer: EventWriter<PlaySfx>,
         action: Query<
             (&Interaction, &MenuButtonAction, &mut BackgroundColor),
             (Changed<Interaction>, With<Button>),
         >,
         mut app_exit_events: EventWriter<AppExit>,
         mut app_state: ResMut<NextState<GameState>>,
+        mut menu_screen: ResMut<NextState<MenuScreen>>,
+        mut rng: ResMut<GameRng>,
+        mut seed_entry: ResMut<SeedEntry>,
     ) {
         for (interaction, menu_action, mut background_color) in action {
             if *interaction == Interaction::Pressed {
-                commands.spawn((
-                    AudioPlayer::new(asset_server.load("sounds/button_select.wav")),
-                    PlaybackSettings {
-                        volume: bevy::audio::Volume::Linear(0.5),
-                        ..Default::default()
-                    },
-                ));
+                sfx_writer.write(PlaySfx(SfxKind::ButtonSelect));
                 match menu_action {
                     MenuButtonAction::Exit => {
                         app_exit_events.write(AppExit::Success);
@@ -186,11 +358,40 @@ impl MenuPlugin {
                     MenuButtonAction::Play => {
                         app_state.set(GameState::Game);
                     }
+                    MenuButtonAction::Campaign => {
+                        menu_screen.set(MenuScreen::LevelSelect);
+                    }
+                    MenuButtonAction::DailyChallenge => {
+                        menu_screen.set(MenuScreen::Daily);
+                    }
+                    MenuButtonAction::Versus => {
+                        menu_screen.set(MenuScreen::Versus);
+                    }
+                    MenuButtonAction::Editor => {
+                        app_state.set(GameState::Editor);
+                    }
+                    MenuButtonAction::Controls => {
+                        menu_screen.set(MenuScreen::Controls);
+                    }
+                    MenuButtonAction::Settings => {
+                        settings::spawn_settings_screen(&mut commands, &asset_server);
+                    }
+                    MenuButtonAction::NewSeed => {
+                        seed_entry.0 = None;
+                        *rng = GameRng::default();
+                    }
+                    MenuButtonAction::TypeSeed => {
+                        seed_entry.0 = Some(String::new());
+                    }
+                    MenuButtonAction::BackToMenu => {
+                        menu_screen.set(MenuScreen::Main);
+                    }
+                    _ => {
+                        // Not on this screen
+                    }
                 }
             } else if *interaction == Interaction::Hovered {
-                commands.spawn((AudioPlayer::new(
-                    asset_server.load("sounds/button_hover.wav"),
-                ),));
+                sfx_writer.write(PlaySfx(SfxKind::ButtonHover));
             }
 
             *background_color = match interaction {
@@ -200,9 +401,67 @@ impl MenuPlugin {
             }
         }
     }
+
+    /// Type the digits of a seed, like the one from a bug report, [ENTER] to use it and [ESC] to
+    /// cancel.
+    fn type_seed(
+        mut keyboard_reader: EventReader<KeyboardInput>,
+        mut entry: ResMut<SeedEntry>,
+        mut rng: ResMut<GameRng>,
+    ) {
+        for event in keyboard_reader.read() {
+            if !event.state.is_pressed() {
+                continue;
+            }
+            let Some(digits) = entry.0.as_mut() else {
+                return;
+            };
+            match &event.logical_key {
+                Key::Character(text)
+                    if text.chars().all(|c| c.is_ascii_digit())
+                        && digits.len() + text.len() <= MAX_SEED_DIGITS =>
+                {
+                    digits.push_str(text);
+                }
+                Key::Backspace => {
+                    digits.pop();
+                }
+                Key::Escape => entry.0 = None,
+                Key::Enter => {
+                    let digits = entry.0.take().unwrap_or_default();
+                    match digits.parse() {
+                        Ok(seed) => {
+                            info!("Seed set to {seed}");
+                            rng.reseed(seed);
+                        }
+                        Err(err) => warn!("Invalid seed `{digits}`: {err}"),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn update_seed_label(
+        rng: Res<GameRng>,
+        entry: Res<SeedEntry>,
+        mut label: Single<&mut Text, With<SeedLabel>>,
+    ) {
+        label.0 = seed_text(&rng, &entry);
+    }
+}
+
+fn seed_text(rng: &GameRng, entry: &SeedEntry) -> String {
+    match &entry.0 {
+        Some(digits) => format!("Seed: {digits}_"),
+        None => format!("Seed: {}", rng.seed()),
+    }
 }
 
-fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
+pub(crate) fn despawn_screen<T: Component>(
+    to_despawn: Query<Entity, With<T>>,
+    mut commands: Commands,
+) {
     for entity in &to_despawn {
         commands.entity(entity).despawn();
     }
@@ -1,6 +1,15 @@
-use bevy::{diagnostic::FrameCount, input::common_conditions::input_just_pressed, prelude::*};
+use bevy::prelude::*;
 
-use crate::{GameState, menu::MenuButtonAction, player::record_position::RecordedPositions};
+use crate::{
+    GameState,
+    audio::{PlaySfx, SfxKind},
+    campaign::CampaignRun,
+    input::{Action, action_just_pressed, gamepad_just_pressed},
+    menu::MenuButtonAction,
+    modes::{RestartLoop, RestartRun},
+    player::replay_file::{LoadReplay, SaveReplay, latest_replay},
+    versus::{StartVersus, VersusMatch},
+};
 
 pub struct PausePlugin;
 
@@ -8,7 +17,9 @@ impl Plugin for PausePlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            Self::handle_pause.run_if(input_just_pressed(KeyCode::Escape)),
+            Self::handle_pause.run_if(
+                action_just_pressed(Action::Pause).or(gamepad_just_pressed(GamepadButton::Start)),
+            ),
         )
         .add_systems(OnEnter(GameState::Paused), Self::spawn_pause_menu)
         .add_systems(
@@ -18,17 +29,13 @@ impl Plugin for PausePlugin {
         .add_systems(
             OnExit(GameState::Paused),
             super::despawn_screen::<PauseMenuMarker>,
-        )
-        .init_resource::<FramePaused>();
+        );
     }
 }
 
 const NORMAL_BUTTON: Color = Color::srgba(0.25, 0.25, 0.25, 0.08);
 const HOVERED_BUTTON: Color = Color::srgba(0.25, 0.25, 0.25, 0.15);
 
-#[derive(Resource, Default)]
-struct FramePaused(u32);
-
 #[derive(Component)]
 struct PauseMenuMarker;
 
@@ -37,24 +44,14 @@ impl PausePlugin {
         mut set_state: ResMut<NextState<GameState>>,
         get_state: Res<State<GameState>>,
         mut time: ResMut<Time<Virtual>>,
-        mut positions: ResMut<RecordedPositions>,
-        frame: Res<FrameCount>,
-        mut frame_paused: ResMut<FramePaused>,
     ) {
         match get_state.get() {
             GameState::Game => {
                 set_state.set(GameState::Paused);
-                frame_paused.0 = frame.0;
                 time.pause();
             }
             GameState::Paused => {
-                Self::unpause(
-                    &mut set_state,
-                    &mut time,
-                    &mut positions,
-                    &frame,
-                    &frame_paused,
-                );
+                Self::unpause(&mut set_state, &mut time);
             }
             _ => (),
         }
@@ -66,7 +63,7 @@ impl PausePlugin {
         let button_node = Node {
             width: Val::Px(400.),
             height: Val::Px(70.),
-            margin: UiRect::vertical(Val::Px(20.)),
+            margin: UiRect::vertical(Val::Px(10.)),
             justify_content: JustifyContent::End,
             align_items: AlignItems::Center,
             ..Default::default()
@@ -115,6 +112,102 @@ impl PausePlugin {
                         }
                     )],
                 ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BackgroundColor(NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::RestartLoop,
+                    children![(
+                        Node {
+                            margin: UiRect::right(Val::Px(20.)),
+                            ..Default::default()
+                        },
+                        Text::new("Restart loop"),
+                        button_text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BackgroundColor(NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::RestartLevel,
+                    children![(
+                        Node {
+                            margin: UiRect::right(Val::Px(20.)),
+                            ..Default::default()
+                        },
+                        Text::new("Restart level"),
+                        button_text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BackgroundColor(NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::SaveReplay,
+                    children![(
+                        Node {
+                            margin: UiRect::right(Val::Px(20.)),
+                            ..Default::default()
+                        },
+                        Text::new("Save replay"),
+                        button_text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BackgroundColor(NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::LoadReplay,
+                    children![(
+                        Node {
+                            margin: UiRect::right(Val::Px(20.)),
+                            ..Default::default()
+                        },
+                        Text::new("Load replay"),
+                        button_text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BackgroundColor(NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::Settings,
+                    children![(
+                        Node {
+                            margin: UiRect::right(Val::Px(20.)),
+                            ..Default::default()
+                        },
+                        Text::new("Settings"),
+                        button_text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BackgroundColor(NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::MainMenu,
+                    children![(
+                        Node {
+                            margin: UiRect::right(Val::Px(20.)),
+                            ..Default::default()
+                        },
+                        Text::new("Main menu"),
+                        button_text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
                 (
                     Button,
                     button_node.clone(),
@@ -138,40 +231,75 @@ impl PausePlugin {
     fn pause_menu_action(
         mut commands: Commands,
         asset_server: Res<AssetServer>,
+        mut sfx_writer: EventWriter<PlaySfx>,
         action: Query<
             (&Interaction, &MenuButtonAction, &mut BackgroundColor),
             (Changed<Interaction>, With<Button>),
         >,
         mut app_exit_events: EventWriter<AppExit>,
+        mut save_replay_writer: EventWriter<SaveReplay>,
+        mut load_replay_writer: EventWriter<LoadReplay>,
+        mut restart_loop_writer: EventWriter<RestartLoop>,
+        mut restart_run_writer: EventWriter<RestartRun>,
+        mut start_versus_writer: EventWriter<StartVersus>,
+        versus: Option<Res<VersusMatch>>,
+        mut campaign: ResMut<CampaignRun>,
         mut app_state: ResMut<NextState<GameState>>,
         mut time: ResMut<Time<Virtual>>,
-        mut positions: ResMut<RecordedPositions>,
-        frame: Res<FrameCount>,
-        frame_paused: ResMut<FramePaused>,
     ) {
         for (interaction, menu_action, mut background_color) in action {
             if *interaction == Interaction::Pressed {
-                commands.spawn(AudioPlayer::new(
-                    asset_server.load("sounds/button_select.wav"),
-                ));
+                sfx_writer.write(PlaySfx(SfxKind::ButtonSelect));
                 match menu_action {
                     MenuButtonAction::Exit => {
                         app_exit_events.write(AppExit::Success);
                     }
+                    MenuButtonAction::SaveReplay => {
+                        save_replay_writer.write(SaveReplay);
+                    }
+                    MenuButtonAction::LoadReplay => match latest_replay() {
+                        Some(path) => {
+                            load_replay_writer.write(LoadReplay(path));
+                        }
+                        None => warn!("There's no saved replay to load"),
+                    },
+                    MenuButtonAction::Settings => {
+                        super::settings::spawn_settings_screen(&mut commands, &asset_server);
+                    }
                     MenuButtonAction::Play => {
-                        Self::unpause(
-                            &mut app_state,
-                            &mut time,
-                            &mut positions,
-                            &frame,
-                            &frame_paused,
-                        );
+                        Self::unpause(&mut app_state, &mut time);
+                    }
+                    MenuButtonAction::RestartLoop => {
+                        restart_loop_writer.write(RestartLoop);
+                        Self::unpause(&mut app_state, &mut time);
+                    }
+                    MenuButtonAction::RestartLevel => {
+                        // A versus match starts over from the first round
+                        match &versus {
+                            Some(versus) => {
+                                start_versus_writer.write(StartVersus {
+                                    players: versus.players.clone(),
+                                    rounds: versus.rounds,
+                                });
+                            }
+                            None => {
+                                restart_run_writer.write(RestartRun);
+                            }
+                        }
+                        Self::unpause(&mut app_state, &mut time);
+                    }
+                    MenuButtonAction::MainMenu => {
+                        // Straight to the main menu, not the level select of the campaign
+                        campaign.0 = None;
+                        app_state.set(GameState::Menu);
+                        time.unpause();
+                    }
+                    _ => {
+                        // Not on this screen
                     }
                 }
             } else if *interaction == Interaction::Hovered {
-                commands.spawn((AudioPlayer::new(
-                    asset_server.load("sounds/button_hover.wav"),
-                ),));
+                sfx_writer.write(PlaySfx(SfxKind::ButtonHover));
             }
 
             *background_color = match interaction {
@@ -182,15 +310,8 @@ impl PausePlugin {
         }
     }
 
-    fn unpause(
-        set_state: &mut NextState<GameState>,
-        time: &mut Time<Virtual>,
-        positions: &mut RecordedPositions,
-        frame: &FrameCount,
-        frame_paused: &FramePaused,
-    ) {
-        let frames_since_pause = frame.0 - frame_paused.0;
-        positions.frame_start += frames_since_pause;
+    /// The simulation tick stood still while paused, so timings pick up where they left off.
+    fn unpause(set_state: &mut NextState<GameState>, time: &mut Time<Virtual>) {
         set_state.set(GameState::Game);
         time.unpause();
     }
@@ -0,0 +1,308 @@
+use bevy::{prelude::*, ui::FocusPolicy};
+
+use crate::{
+    GameState,
+    audio::{PlaySfx, SfxCategory, SfxKind},
+    settings::Settings,
+};
+
+/// Settings screen, opened on top of the main menu or the pause menu.
+pub struct SettingsScreenPlugin;
+
+impl Plugin for SettingsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (Self::settings_action, Self::update_labels)
+                .chain()
+                .run_if(any_with_component::<SettingsMarker>),
+        )
+        .add_systems(
+            OnExit(GameState::Menu),
+            super::despawn_screen::<SettingsMarker>,
+        )
+        .add_systems(
+            OnExit(GameState::Paused),
+            super::despawn_screen::<SettingsMarker>,
+        );
+    }
+}
+
+/// How much the volume changes with each click
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Component)]
+struct SettingsMarker;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsButton {
+    VolumeDown(VolumeSetting),
+    VolumeUp(VolumeSetting),
+    Fullscreen,
+    Vsync,
+    FrameCap,
+    ReplayAllRuns,
+    Course,
+    Back,
+}
+
+/// Which setting a text shows the value of
+#[derive(Component, Debug, Clone, Copy)]
+enum SettingsLabel {
+    Volume(VolumeSetting),
+    Fullscreen,
+    Vsync,
+    FrameCap,
+    ReplayAllRuns,
+    Course,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VolumeSetting {
+    Music,
+    Sfx,
+    /// Sound effects of a category, on top of [`VolumeSetting::Sfx`]
+    Bus(SfxCategory),
+}
+
+impl VolumeSetting {
+    fn get(&self, settings: &Settings) -> f32 {
+        match self {
+            VolumeSetting::Music => settings.music_volume,
+            VolumeSetting::Sfx => settings.sfx_volume,
+            VolumeSetting::Bus(category) => settings.bus_volume(*category),
+        }
+    }
+
+    fn set(&self, settings: &mut Settings, volume: f32) {
+        match self {
+            VolumeSetting::Music => settings.music_volume = volume,
+            VolumeSetting::Sfx => settings.sfx_volume = volume,
+            VolumeSetting::Bus(category) => settings.set_bus_volume(*category, volume),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            VolumeSetting::Music => "Music".to_string(),
+            VolumeSetting::Sfx => "Effects".to_string(),
+            VolumeSetting::Bus(category) => format!("{} effects", category.label()),
+        }
+    }
+}
+
+pub(super) fn spawn_settings_screen(commands: &mut Commands, asset_server: &AssetServer) {
+    let title_font = asset_server.load(super::TITLE_FONT_PATH);
+    let text_font = TextFont {
+        font_size: 28.,
+        font: asset_server.load("fonts/capitolcity.ttf"),
+        ..Default::default()
+    };
+    let wide_button = Node {
+        width: Val::Px(500.),
+        height: Val::Px(55.),
+        margin: UiRect::all(Val::Px(8.)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        border: UiRect::all(Val::Px(3.)),
+        ..Default::default()
+    };
+    let small_button = Node {
+        width: Val::Px(55.),
+        ..wide_button.clone()
+    };
+    let label_node = Node {
+        width: Val::Px(374.),
+        justify_content: JustifyContent::Center,
+        ..Default::default()
+    };
+
+    commands
+        .spawn((
+            SettingsMarker,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            // Drawn over the menu it was opened from, without letting it be clicked
+            GlobalZIndex(10),
+            FocusPolicy::Block,
+            BackgroundColor(Color::srgb(0., 0., 0.)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Settings"),
+                TextFont {
+                    font_size: 80.,
+                    font: title_font,
+                    ..Default::default()
+                },
+                TextColor(super::TEXT_COLOR),
+                Node {
+                    margin: UiRect::all(Val::Px(30.)),
+                    ..Default::default()
+                },
+            ));
+
+            // Volumes, with buttons on each side
+            let volumes = [VolumeSetting::Music, VolumeSetting::Sfx]
+                .into_iter()
+                .chain(SfxCategory::ALL.map(VolumeSetting::Bus));
+            for volume in volumes {
+                parent
+                    .spawn(Node {
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            Button,
+                            small_button.clone(),
+                            BackgroundColor(super::NORMAL_BUTTON),
+                            BorderColor(Color::BLACK),
+                            SettingsButton::VolumeDown(volume),
+                            children![(
+                                Text::new("-"),
+                                text_font.clone(),
+                                TextColor(super::TEXT_COLOR)
+                            )],
+                        ));
+                        row.spawn((
+                            label_node.clone(),
+                            children![(
+                                Text::default(),
+                                SettingsLabel::Volume(volume),
+                                text_font.clone(),
+                                TextColor(super::TEXT_COLOR)
+                            )],
+                        ));
+                        row.spawn((
+                            Button,
+                            small_button.clone(),
+                            BackgroundColor(super::NORMAL_BUTTON),
+                            BorderColor(Color::BLACK),
+                            SettingsButton::VolumeUp(volume),
+                            children![(
+                                Text::new("+"),
+                                text_font.clone(),
+                                TextColor(super::TEXT_COLOR)
+                            )],
+                        ));
+                    });
+            }
+
+            // Everything else changes when clicked
+            for (button, label) in [
+                (SettingsButton::Fullscreen, SettingsLabel::Fullscreen),
+                (SettingsButton::Vsync, SettingsLabel::Vsync),
+                (SettingsButton::FrameCap, SettingsLabel::FrameCap),
+                (SettingsButton::ReplayAllRuns, SettingsLabel::ReplayAllRuns),
+                (SettingsButton::Course, SettingsLabel::Course),
+            ] {
+                parent.spawn((
+                    Button,
+                    wide_button.clone(),
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    button,
+                    children![(
+                        Text::default(),
+                        label,
+                        text_font.clone(),
+                        TextColor(super::TEXT_COLOR)
+                    )],
+                ));
+            }
+
+            parent.spawn((
+                Button,
+                wide_button,
+                BackgroundColor(super::NORMAL_BUTTON),
+                BorderColor(Color::BLACK),
+                SettingsButton::Back,
+                children![(Text::new("Back"), text_font, TextColor(super::TEXT_COLOR))],
+            ));
+        });
+}
+
+impl SettingsScreenPlugin {
+    fn settings_action(
+        mut commands: Commands,
+        mut sfx_writer: EventWriter<PlaySfx>,
+        action: Query<
+            (&Interaction, &SettingsButton, &mut BackgroundColor),
+            (Changed<Interaction>, With<Button>),
+        >,
+        screen: Single<Entity, With<SettingsMarker>>,
+        mut settings: ResMut<Settings>,
+    ) {
+        for (interaction, settings_action, mut background_color) in action {
+            if *interaction == Interaction::Pressed {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonSelect));
+                let mut volume_step = |volume: &VolumeSetting, step: f32| {
+                    let new_volume = (volume.get(&settings) + step).clamp(0., 1.);
+                    volume.set(&mut settings, new_volume);
+                };
+                match settings_action {
+                    SettingsButton::VolumeDown(volume) => volume_step(volume, -VOLUME_STEP),
+                    SettingsButton::VolumeUp(volume) => volume_step(volume, VOLUME_STEP),
+                    SettingsButton::Fullscreen => {
+                        settings.fullscreen = !settings.fullscreen;
+                    }
+                    SettingsButton::Vsync => {
+                        settings.vsync = !settings.vsync;
+                    }
+                    SettingsButton::FrameCap => {
+                        settings.frame_cap = settings.frame_cap.next();
+                    }
+                    SettingsButton::ReplayAllRuns => {
+                        settings.replay_all_runs = !settings.replay_all_runs;
+                    }
+                    SettingsButton::Course => {
+                        settings.course = settings.course.next();
+                    }
+                    SettingsButton::Back => {
+                        commands.entity(*screen).despawn();
+                        continue;
+                    }
+                }
+                settings.save();
+            } else if *interaction == Interaction::Hovered {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonHover));
+            }
+
+            *background_color = match interaction {
+                Interaction::None => super::NORMAL_BUTTON.into(),
+                Interaction::Pressed => super::PRESSED_BUTTON.into(),
+                Interaction::Hovered => super::HOVERED_BUTTON.into(),
+            }
+        }
+    }
+
+    fn update_labels(settings: Res<Settings>, labels: Query<(&mut Text, &SettingsLabel)>) {
+        let on_off = |on: bool| if on { "On" } else { "Off" };
+        for (mut text, label) in labels {
+            text.0 = match label {
+                SettingsLabel::Volume(volume) => {
+                    format!("{}: {:.0}%", volume.label(), volume.get(&settings) * 100.)
+                }
+                SettingsLabel::Fullscreen => {
+                    format!("Fullscreen: {}", on_off(settings.fullscreen))
+                }
+                SettingsLabel::Vsync => format!("VSync: {}", on_off(settings.vsync)),
+                SettingsLabel::FrameCap => {
+                    format!("Frame cap: {}", settings.frame_cap.label())
+                }
+                SettingsLabel::ReplayAllRuns => {
+                    format!("Replay all past runs: {}", on_off(settings.replay_all_runs))
+                }
+                SettingsLabel::Course => format!("Course: {}", settings.course.label()),
+            };
+        }
+    }
+}
@@ -0,0 +1,450 @@
+use bevy::{
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+};
+
+use crate::{
+    GameState,
+    audio::{PlaySfx, SfxKind},
+    menu::{MenuButtonAction, MenuScreen},
+    versus::{Profiles, StartVersus, VersusMatch},
+};
+
+/// How many rounds a match can last.
+const ROUND_CHOICES: [u32; 3] = [1, 3, 5];
+/// Longest name a profile can have
+const MAX_NAME_LENGTH: usize = 12;
+
+/// Screen to pick who plays a versus match, and the screen at the end of it.
+pub struct VersusScreenPlugin;
+
+impl Plugin for VersusScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VersusSetup>()
+            .add_systems(OnEnter(MenuScreen::Versus), Self::spawn_versus_screen)
+            .add_systems(
+                Update,
+                (
+                    Self::versus_action,
+                    Self::type_name.run_if(|setup: Res<VersusSetup>| setup.new_name.is_some()),
+                    Self::update_labels,
+                )
+                    .chain()
+                    .run_if(in_state(MenuScreen::Versus)),
+            )
+            .add_systems(
+                OnExit(MenuScreen::Versus),
+                (
+                    super::despawn_screen::<VersusMarker>,
+                    |mut setup: ResMut<VersusSetup>| setup.new_name = None,
+                ),
+            )
+            .add_systems(OnEnter(GameState::MatchOver), Self::spawn_match_over_screen)
+            .add_systems(
+                Update,
+                Self::match_over_action.run_if(in_state(GameState::MatchOver)),
+            )
+            .add_systems(
+                OnExit(GameState::MatchOver),
+                super::despawn_screen::<MatchOverMarker>,
+            );
+    }
+}
+
+#[derive(Component)]
+struct VersusMarker;
+
+#[derive(Component)]
+struct MatchOverMarker;
+
+#[derive(Component, Debug)]
+enum VersusButton {
+    /// Pick the next profile for the player running first (0) or defending first (1)
+    Player(usize),
+    Rounds,
+    NewProfile,
+    Start,
+}
+
+#[derive(Component, Debug)]
+enum VersusLabel {
+    Player(usize),
+    Rounds,
+    NewProfile,
+}
+
+/// Choices made on the versus screen.
+#[derive(Resource, Debug)]
+struct VersusSetup {
+    /// Index of each player's profile
+    players: [usize; 2],
+    /// Index in [`ROUND_CHOICES`]
+    rounds: usize,
+    /// Name of the profile being typed in, if one is being created
+    new_name: Option<String>,
+}
+
+impl Default for VersusSetup {
+    fn default() -> Self {
+        Self {
+            players: [0, 1],
+            rounds: 1,
+            new_name: None,
+        }
+    }
+}
+
+impl VersusScreenPlugin {
+    fn spawn_versus_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+        let title_font = asset_server.load(super::TITLE_FONT_PATH);
+        let button_text_font = TextFont {
+            font_size: 24.,
+            font: asset_server.load("fonts/capitolcity.ttf"),
+            ..Default::default()
+        };
+        let row_node = Node {
+            width: Val::Px(600.),
+            height: Val::Px(45.),
+            margin: UiRect::all(Val::Px(5.)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            border: UiRect::all(Val::Px(3.)),
+            ..Default::default()
+        };
+        let help_text = concat!(
+            "One player runs, the other stops the replay of that run. Then swap!\n",
+            "Click a player to pick another profile."
+        );
+
+        commands
+            .spawn((
+                VersusMarker,
+                Node {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..Default::default()
+                },
+                BackgroundColor(Color::srgb(0., 0., 0.)),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Text::new("Versus"),
+                    TextFont {
+                        font_size: 80.,
+                        font: title_font,
+                        ..Default::default()
+                    },
+                    TextColor(super::TEXT_COLOR),
+                    Node {
+                        margin: UiRect::all(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                ));
+                parent.spawn((
+                    Text::new(help_text),
+                    button_text_font.clone(),
+                    TextColor(super::TEXT_COLOR),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                    Node {
+                        margin: UiRect::bottom(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                ));
+                for (action, label) in [
+                    (VersusButton::Player(0), VersusLabel::Player(0)),
+                    (VersusButton::Player(1), VersusLabel::Player(1)),
+                    (VersusButton::Rounds, VersusLabel::Rounds),
+                    (VersusButton::NewProfile, VersusLabel::NewProfile),
+                ] {
+                    parent.spawn((
+                        Button,
+                        row_node.clone(),
+                        BackgroundColor(super::NORMAL_BUTTON),
+                        BorderColor(Color::BLACK),
+                        action,
+                        children![(
+                            Text::default(),
+                            label,
+                            button_text_font.clone(),
+                            TextColor(super::TEXT_COLOR),
+                        )],
+                    ));
+                }
+                parent.spawn((
+                    Button,
+                    row_node.clone(),
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    VersusButton::Start,
+                    children![(
+                        Text::new("Start"),
+                        button_text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ));
+                parent.spawn((
+                    Button,
+                    row_node,
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::BackToMenu,
+                    children![(
+                        Text::new("Back"),
+                        button_text_font,
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ));
+            });
+    }
+
+    fn versus_action(
+        mut sfx_writer: EventWriter<PlaySfx>,
+        action: Query<
+            (&Interaction, &VersusButton, &mut BackgroundColor),
+            (Changed<Interaction>, With<Button>),
+        >,
+        mut setup: ResMut<VersusSetup>,
+        profiles: Res<Profiles>,
+        mut start_writer: EventWriter<StartVersus>,
+    ) {
+        for (interaction, versus_action, mut background_color) in action {
+            if *interaction == Interaction::Pressed {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonSelect));
+                match versus_action {
+                    VersusButton::Player(player) => {
+                        // Skip the profile the other player picked
+                        let other = setup.players[1 - player];
+                        let mut next = (setup.players[*player] + 1) % profiles.0.len();
+                        if next == other {
+                            next = (next + 1) % profiles.0.len();
+                        }
+                        setup.players[*player] = next;
+                    }
+                    VersusButton::Rounds => {
+                        setup.rounds = (setup.rounds + 1) % ROUND_CHOICES.len();
+                    }
+                    VersusButton::NewProfile => {
+                        setup.new_name = Some(String::new());
+                    }
+                    VersusButton::Start => {
+                        start_writer.write(StartVersus {
+                            players: setup
+                                .players
+                                .map(|index| profiles.0[index % profiles.0.len()].name.clone()),
+                            rounds: ROUND_CHOICES[setup.rounds],
+                        });
+                    }
+                }
+            } else if *interaction == Interaction::Hovered {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonHover));
+            }
+
+            *background_color = match interaction {
+                Interaction::None => super::NORMAL_BUTTON.into(),
+                Interaction::Pressed => super::PRESSED_BUTTON.into(),
+                Interaction::Hovered => super::HOVERED_BUTTON.into(),
+            }
+        }
+    }
+
+    /// Type the name of a new profile, [ENTER] to create it and [ESC] to cancel.
+    fn type_name(
+        mut keyboard_reader: EventReader<KeyboardInput>,
+        mut setup: ResMut<VersusSetup>,
+        mut profiles: ResMut<Profiles>,
+    ) {
+        for event in keyboard_reader.read() {
+            if !event.state.is_pressed() {
+                continue;
+            }
+            let Some(name) = setup.new_name.as_mut() else {
+                return;
+            };
+            match &event.logical_key {
+                Key::Character(text) if name.chars().count() < MAX_NAME_LENGTH => {
+                    name.push_str(text);
+                }
+                Key::Space if name.chars().count() < MAX_NAME_LENGTH => name.push(' '),
+                Key::Backspace => {
+                    name.pop();
+                }
+                Key::Escape => setup.new_name = None,
+                Key::Enter => {
+                    let name = setup.new_name.take().unwrap_or_default();
+                    let name = name.trim();
+                    if name.is_empty() {
+                        continue;
+                    }
+                    info!("Adding profile {name}");
+                    profiles.add(name.to_string());
+                    profiles.save();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn update_labels(
+        setup: Res<VersusSetup>,
+        profiles: Res<Profiles>,
+        labels: Query<(&mut Text, &VersusLabel)>,
+    ) {
+        for (mut text, label) in labels {
+            text.0 = match label {
+                VersusLabel::Player(player) => {
+                    let profile = &profiles.0[setup.players[*player] % profiles.0.len()];
+                    let role = if *player == 0 {
+                        "Runs first"
+                    } else {
+                        "Defends first"
+                    };
+                    format!(
+                        "{role}: {} ({}/{} won)",
+                        profile.name, profile.matches_won, profile.matches_played
+                    )
+                }
+                VersusLabel::Rounds => format!("Rounds: {}", ROUND_CHOICES[setup.rounds]),
+                VersusLabel::NewProfile => match &setup.new_name {
+                    Some(name) => format!("Name: {name}_"),
+                    None => "New profile".to_string(),
+                },
+            };
+        }
+    }
+
+    fn spawn_match_over_screen(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        versus: Res<VersusMatch>,
+    ) {
+        let title_font = asset_server.load(super::TITLE_FONT_PATH);
+        let text_font = TextFont {
+            font_size: 24.,
+            font: asset_server.load("fonts/capitolcity.ttf"),
+            ..Default::default()
+        };
+        let button_node = Node {
+            width: Val::Px(300.),
+            height: Val::Px(70.),
+            margin: UiRect::all(Val::Px(20.)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            border: UiRect::all(Val::Px(3.)),
+            ..Default::default()
+        };
+        let button_text_font = TextFont {
+            font_size: 33.,
+            ..text_font.clone()
+        };
+        let result = match versus.winner() {
+            Some(winner) => format!("{} wins!", versus.players[winner]),
+            None => "It's a draw!".to_string(),
+        };
+
+        commands.spawn((
+            MatchOverMarker,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            BackgroundColor(Color::srgba(0., 0., 0., 0.8)),
+            children![
+                (
+                    Text::new(result),
+                    TextFont {
+                        font_size: 80.,
+                        font: title_font,
+                        ..Default::default()
+                    },
+                    TextColor(super::TEXT_COLOR),
+                    Node {
+                        margin: UiRect::all(Val::Px(30.)),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    Text::new(format!(
+                        "{} {} - {} {}   after {} rounds",
+                        versus.players[0],
+                        versus.scores[0],
+                        versus.scores[1],
+                        versus.players[1],
+                        versus.rounds
+                    )),
+                    text_font,
+                    TextColor(super::TEXT_COLOR),
+                ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::Rematch,
+                    children![(
+                        Text::new("Rematch"),
+                        button_text_font.clone(),
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
+                (
+                    Button,
+                    button_node,
+                    BackgroundColor(super::NORMAL_BUTTON),
+                    BorderColor(Color::BLACK),
+                    MenuButtonAction::Exit,
+                    children![(
+                        Text::new("Exit"),
+                        button_text_font,
+                        TextColor(super::TEXT_COLOR),
+                    )],
+                ),
+            ],
+        ));
+    }
+
+    fn match_over_action(
+        mut sfx_writer: EventWriter<PlaySfx>,
+        action: Query<
+            (&Interaction, &MenuButtonAction, &mut BackgroundColor),
+            (Changed<Interaction>, With<Button>),
+        >,
+        versus: Res<VersusMatch>,
+        mut start_writer: EventWriter<StartVersus>,
+        mut app_exit_events: EventWriter<AppExit>,
+    ) {
+        for (interaction, menu_action, mut background_color) in action {
+            if *interaction == Interaction::Pressed {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonSelect));
+                match menu_action {
+                    MenuButtonAction::Exit => {
+                        app_exit_events.write(AppExit::Success);
+                    }
+                    MenuButtonAction::Rematch => {
+                        start_writer.write(StartVersus {
+                            players: versus.players.clone(),
+                            rounds: versus.rounds,
+                        });
+                    }
+                    _ => {
+                        // Not on this screen
+                    }
+                }
+            } else if *interaction == Interaction::Hovered {
+                sfx_writer.write(PlaySfx(SfxKind::ButtonHover));
+            }
+
+            *background_color = match interaction {
+                Interaction::None => super::NORMAL_BUTTON.into(),
+                Interaction::Pressed => super::PRESSED_BUTTON.into(),
+                Interaction::Hovered => super::HOVERED_BUTTON.into(),
+            }
+        }
+    }
+}
@@ -1,16 +1,25 @@
 use bevy::{
-    diagnostic::FrameCount, ecs::entity_disabling::Disabled,
-    input::common_conditions::input_pressed, prelude::*,
+    ecs::{entity_disabling::Disabled, system::SystemParam},
+    prelude::*,
 };
 
 use crate::{
-    GameState,
+    GameState, SimulationTick,
+    audio::{PlaySfx, SfxKind},
+    environment::LevelGeometry,
     environment::ResetEnvironment,
+    input::{Action, action_pressed, gamepad_pressed},
     obstacles::{
-        Flicker, GhostObstacle, LastInsertedObstacle, SpaceToContinueMarker,
-        SpawnGhostObstacleEvent,
+        Companions, Flicker, GhostObstacle, LastInsertedObstacle, ObstacleMarker, PlacementPoints,
+        SpaceToContinueMarker,
     },
-    player::record_position::{RecordPositionPlugin, RecordedPositions},
+    player::{
+        Player,
+        past_runs::{PastRun, PastRuns},
+        record_movement::RecordedMovements,
+        record_position::{RecordPositionPlugin, RecordedPositions},
+    },
+    timeline::TimelineMarker,
 };
 
 /// The two modes for the game
@@ -28,6 +37,66 @@ pub enum GameMode {
 #[derive(Debug, Event)]
 pub struct GoalReached;
 
+/// Start the run over: remove every obstacle the player placed and go back to survive mode.
+#[derive(Debug, Event)]
+pub struct RestartRun;
+
+/// Start the current loop over: back to survive mode with the obstacles that were there when it
+/// started, without losing a life.
+#[derive(Debug, Event)]
+pub struct RestartLoop;
+
+/// Going back to survive mode to restart, rather than because the replay was stopped. Removed once
+/// survive mode is entered.
+#[derive(Debug, Resource)]
+pub struct Restarting;
+
+/// The last obstacle placed when the current loop's run reached the goal, to tell it apart from
+/// the one placed since.
+#[derive(Debug, Resource, Default)]
+struct LoopStart {
+    last_obstacle: Option<Entity>,
+}
+
+/// What a run leaves behind besides the level: the obstacles placed along the way, and what's shown
+/// between loops.
+#[derive(SystemParam)]
+struct RunEntities<'w, 's> {
+    placed_obstacles: Query<
+        'w,
+        's,
+        (Entity, &'static Companions),
+        (With<ObstacleMarker>, Without<LevelGeometry>),
+    >,
+    space_to_continue: Query<'w, 's, Entity, With<SpaceToContinueMarker>>,
+    past_runs: Query<'w, 's, Entity, With<PastRun>>,
+    timeline: Query<'w, 's, Entity, With<TimelineMarker>>,
+}
+
+impl RunEntities<'_, '_> {
+    fn despawn(&self, commands: &mut Commands) {
+        for (obstacle, companions) in &self.placed_obstacles {
+            despawn_obstacle(commands, obstacle, companions);
+        }
+        for entity in self
+            .space_to_continue
+            .iter()
+            .chain(&self.past_runs)
+            .chain(&self.timeline)
+        {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Despawn an obstacle along with the entities spawned for it, like the shadow of a laser.
+fn despawn_obstacle(commands: &mut Commands, obstacle: Entity, companions: &Companions) {
+    commands.entity(obstacle).despawn();
+    for &companion in &companions.0 {
+        commands.entity(companion).despawn();
+    }
+}
+
 pub struct ModesManagement;
 
 impl Plugin for ModesManagement {
@@ -40,8 +109,10 @@ impl Plugin for ModesManagement {
                     RecordPositionPlugin::record_position.run_if(in_state(GameMode::Survive)),
                 )
                     .run_if(on_event::<GoalReached>),
-                Self::handle_replay
-                    .run_if(input_pressed(KeyCode::Space).and(in_state(GameMode::Defend))),
+                Self::handle_replay.run_if(
+                    (action_pressed(Action::Continue).or(gamepad_pressed(GamepadButton::Select)))
+                        .and(in_state(GameMode::Defend)),
+                ),
             )
                 .before(crate::update_state)
                 .run_if(in_state(GameState::Game)),
@@ -54,9 +125,25 @@ impl Plugin for ModesManagement {
                     .and(in_state(GameState::Game)),
             ),),
         )
+        .add_systems(
+            FixedPreUpdate,
+            (
+                Self::restart_run.run_if(on_event::<RestartRun>),
+                Self::restart_loop.run_if(on_event::<RestartLoop>),
+            )
+                .before(crate::update_state),
+        )
+        .add_systems(
+            OnEnter(GameState::Menu),
+            Self::leave_game.run_if(any_with_component::<Player>),
+        )
+        .add_systems(OnEnter(GameMode::Survive), Self::done_restarting)
         .add_systems(OnEnter(GameMode::Replay), Self::reset_replay)
         .add_systems(OnEnter(GameMode::Defend), Self::reset_replay)
-        .add_event::<GoalReached>();
+        .init_resource::<LoopStart>()
+        .add_event::<GoalReached>()
+        .add_event::<RestartRun>()
+        .add_event::<RestartLoop>();
     }
 }
 
@@ -75,18 +162,19 @@ impl ModesManagement {
         mut commands: Commands,
         mut state: ResMut<NextState<GameMode>>,
         mut reset_environment: EventWriter<ResetEnvironment>,
-        mut spawn_obstacle_writer: EventWriter<SpawnGhostObstacleEvent>,
         mode: Res<State<GameMode>>,
 
-        asset_server: Res<AssetServer>,
+        mut sfx_writer: EventWriter<PlaySfx>,
         last_placed_obstacle: Option<Single<Entity, With<LastInsertedObstacle>>>,
+        mut loop_start: ResMut<LoopStart>,
     ) {
-        commands.spawn(AudioPlayer::new(asset_server.load("sounds/flag.ogg")));
+        sfx_writer.write(PlaySfx(SfxKind::Flag));
         match mode.get() {
             GameMode::Survive => {
                 info!("flag reached in survive mode");
+                loop_start.last_obstacle = last_placed_obstacle.as_deref().copied();
                 reset_environment.write(ResetEnvironment);
-                spawn_obstacle_writer.write(SpawnGhostObstacleEvent::random());
+                // The shop opens to pick the next obstacle
                 state.set(GameMode::Defend);
             }
             GameMode::Replay => {
@@ -94,7 +182,7 @@ impl ModesManagement {
                 reset_environment.write(ResetEnvironment);
                 commands
                     .entity(last_placed_obstacle.unwrap().into_inner())
-                    .insert(GhostObstacle)
+                    .insert((GhostObstacle, PlacementPoints::default()))
                     .remove::<LastInsertedObstacle>();
                 state.set(GameMode::Defend)
             }
@@ -118,9 +206,101 @@ impl ModesManagement {
         state.set(GameMode::Replay);
     }
 
-    fn reset_replay(mut recorded_positions: ResMut<RecordedPositions>, frame: Res<FrameCount>) {
+    fn restart_run(
+        mut commands: Commands,
+        mode: Res<State<GameMode>>,
+        mut state: ResMut<NextState<GameMode>>,
+        mut reset_environment: EventWriter<ResetEnvironment>,
+        mut recorded_positions: ResMut<RecordedPositions>,
+        mut recorded_movements: ResMut<RecordedMovements>,
+        mut past_runs: ResMut<PastRuns>,
+        run_entities: RunEntities,
+    ) {
+        info!("Restarting the run");
+        run_entities.despawn(&mut commands);
+        recorded_positions.positions.clear();
+        recorded_positions.last_played_frame = 0;
+        recorded_positions.locked = true;
+        recorded_movements.clear();
+        recorded_movements.locked = true;
+        past_runs.0.clear();
+
+        reset_environment.write(ResetEnvironment);
+        if *mode.get() != GameMode::Survive {
+            commands.insert_resource(Restarting);
+        }
+        state.set(GameMode::Survive);
+    }
+
+    fn restart_loop(
+        mut commands: Commands,
+        mode: Res<State<GameMode>>,
+        mut state: ResMut<NextState<GameMode>>,
+        mut reset_environment: EventWriter<ResetEnvironment>,
+        mut recorded_positions: ResMut<RecordedPositions>,
+        mut recorded_movements: ResMut<RecordedMovements>,
+        loop_start: Res<LoopStart>,
+        new_obstacles: Query<
+            (Entity, &Companions, Has<GhostObstacle>),
+            Or<(With<GhostObstacle>, With<LastInsertedObstacle>)>,
+        >,
+        space_to_continue: Query<Entity, With<SpaceToContinueMarker>>,
+    ) {
+        info!("Restarting the loop");
+        if *mode.get() != GameMode::Survive {
+            // The goal was already reached: the obstacle bought since then goes away
+            for (obstacle, companions, ghost) in &new_obstacles {
+                if ghost || Some(obstacle) != loop_start.last_obstacle {
+                    despawn_obstacle(&mut commands, obstacle, companions);
+                }
+            }
+            for entity in &space_to_continue {
+                commands.entity(entity).despawn();
+            }
+            commands.insert_resource(Restarting);
+            state.set(GameMode::Survive);
+        }
+        recorded_positions.positions.clear();
+        recorded_positions.last_played_frame = 0;
+        recorded_positions.locked = true;
+        recorded_movements.clear();
+        recorded_movements.locked = true;
+
+        reset_environment.write(ResetEnvironment);
+    }
+
+    fn done_restarting(mut commands: Commands) {
+        commands.remove_resource::<Restarting>();
+    }
+
+    /// Back in the menu, the level, the player and everything from the run are gone, and the next
+    /// game starts a new run.
+    fn leave_game(
+        mut commands: Commands,
+        mut state: ResMut<NextState<GameMode>>,
+        mut restart_writer: EventWriter<RestartRun>,
+        game_entities: Query<Entity, Or<(With<Player>, With<LevelGeometry>)>>,
+        run_entities: RunEntities,
+    ) {
+        info!("Leaving the game");
+        for entity in &game_entities {
+            commands.entity(entity).despawn();
+        }
+        run_entities.despawn(&mut commands);
+        state.set(GameMode::Survive);
+        // The score, the shop and the recordings are reset along with a new run
+        restart_writer.write(RestartRun);
+    }
+
+    fn reset_replay(
+        mut recorded_positions: ResMut<RecordedPositions>,
+        mut recorded_movements: ResMut<RecordedMovements>,
+        tick: Res<SimulationTick>,
+    ) {
         info!("resetting frame_start");
-        recorded_positions.frame_start = frame.0;
+        recorded_positions.frame_start = tick.0;
         recorded_positions.last_played_frame = 0;
+        recorded_movements.next_tick = 0;
+        recorded_movements.ticks_past_end = 0;
     }
 }
@@ -0,0 +1,426 @@
+//! A laser beam that covers the whole height of the level, but only strikes every once in a while.
+
+use std::ops::RangeInclusive;
+
+use avian2d::prelude::*;
+use bevy::{input::mouse::AccumulatedMouseScroll, prelude::*, sprite::AlphaMode2d};
+
+use crate::{
+    GameState, SimulationTick,
+    audio::{PlaySfx, SfxKind},
+    modes::GameMode,
+    obstacles::{Companions, GhostObstacle, Obstacle, ObstacleSpawner},
+    player::{PlayerDeath, record_position::RecordedPositions},
+};
+
+pub struct Laser;
+
+/// Periods the defender can pick for a laser, in frames
+const PERIOD_RANGE: RangeInclusive<u32> = 60..=240;
+const PERIOD_STEP: u32 = 10;
+/// Shortest time a laser can be on for, in frames
+const MIN_DURATION: u32 = 5;
+const DURATION_STEP: u32 = 5;
+/// Budget of a laser: it can be on for at most this much of its period
+const MAX_DUTY: f32 = 0.25;
+/// How much the phase moves with each step when the replay never goes through the laser, in frames
+const PHASE_STEP: u32 = 5;
+
+/// Marker component for the shadow of a laser
+#[derive(Debug, Component)]
+pub struct FakeLaser;
+
+#[derive(Debug, Component, Clone)]
+pub struct Flicker {
+    /// How many frames between each appearance start, in frames
+    period: u32,
+    /// How long this appears for, in frames
+    duration: u32,
+    /// First frame the laser appears in, it then comes back every period
+    phase: u32,
+}
+
+impl Default for Flicker {
+    fn default() -> Self {
+        Self {
+            period: 120,
+            duration: 20,
+            phase: 120,
+        }
+    }
+}
+
+impl Flicker {
+    /// Whether the laser strikes `frame` frames after the start of the run.
+    pub fn is_active_at(&self, frame: u32) -> bool {
+        frame >= self.phase && (frame - self.phase) % self.period < self.duration
+    }
+
+    /// Longest the laser can be on for with its period, within its budget.
+    fn max_duration(&self) -> u32 {
+        ((self.period as f32 * MAX_DUTY) as u32).max(MIN_DURATION)
+    }
+
+    fn change_period(&mut self, steps: i32) {
+        let period = self
+            .period
+            .saturating_add_signed(steps * PERIOD_STEP as i32);
+        self.period = period.clamp(*PERIOD_RANGE.start(), *PERIOD_RANGE.end());
+        // A shorter period leaves less budget
+        self.duration = self.duration.min(self.max_duration());
+    }
+
+    fn change_duration(&mut self, steps: i32) {
+        let duration = self
+            .duration
+            .saturating_add_signed(steps * DURATION_STEP as i32);
+        self.duration = duration.clamp(MIN_DURATION, self.max_duration());
+    }
+}
+
+/// Picks the phase of the laser being placed: which of the frames the replay goes through the laser
+/// it'll strike at, or if it never does, how many steps of [`PHASE_STEP`] to wait.
+/// Changed with the mouse wheel or the gamepad bumpers. It stays on the laser once placed, so it can
+/// be aimed again when the replay gets past it and it's moved.
+#[derive(Debug, Component, Default)]
+pub struct LaserTarget(usize);
+
+/// Text showing the frame the laser being placed will strike at
+#[derive(Debug, Component)]
+struct StrikeLabel;
+
+impl Obstacle for Laser {
+    fn spawn(
+        &self,
+        spawner: &mut ObstacleSpawner,
+        obstacle: Entity,
+        position: Vec2,
+    ) -> Vec<Entity> {
+        let shadow = spawner
+            .commands
+            .spawn((
+                Mesh2d(spawner.meshes.add(Rectangle {
+                    half_size: vec2(20., 1000.),
+                })),
+                MeshMaterial2d(spawner.materials.add(ColorMaterial {
+                    color: Color::srgb(1.0, 0.2, 0.3).with_alpha(0.2),
+                    alpha_mode: AlphaMode2d::Blend,
+                    ..default()
+                })),
+                Transform::from_translation(position.extend(0.)),
+                FakeLaser,
+            ))
+            .id();
+        spawner.commands.entity(obstacle).insert((
+            Sensor,
+            Collider::rectangle(30.0, 1000.0),
+            Mesh2d(spawner.meshes.add(Rectangle {
+                half_size: vec2(20., 10_000.),
+            })),
+            Flicker::default(),
+            LaserTarget::default(),
+        ));
+        vec![shadow]
+    }
+
+    fn draw_preview(&self, gizmos: &mut Gizmos, position: Vec2, color: Color) {
+        gizmos.line_2d(position.with_y(-1000.), position.with_y(1000.), color);
+    }
+
+    /// Lasers can be timed to strike anywhere along the replay, so they're the most expensive.
+    fn cost(&self) -> u32 {
+        3
+    }
+
+    /// Lasers cover the whole height of the level anyway, so keep them centered.
+    fn constrain_placement(&self, target: Vec2) -> Vec2 {
+        target.with_y(0.)
+    }
+
+    /// Time the laser to strike the player at the frame picked while placing it.
+    fn on_placed(&self, world: &mut World, entity: Entity) {
+        let Ok(laser) = world.get_entity(entity) else {
+            return;
+        };
+        let (Some(transform), Some(collider), Some(target)) = (
+            laser.get::<Transform>(),
+            laser.get::<Collider>(),
+            laser.get::<LaserTarget>(),
+        ) else {
+            return;
+        };
+        let candidates = strike_candidates(
+            collider,
+            transform.translation.truncate(),
+            world.resource::<RecordedPositions>(),
+        );
+        let target = target.0;
+
+        let Some(mut flicker) = world.get_mut::<Flicker>(entity) else {
+            return;
+        };
+        if let Some((frame, position)) = aim(&mut flicker, &candidates, target) {
+            info!("Will strike player in frame {frame}, when they're in position {position}");
+        } else {
+            info!("The replay never goes through the laser");
+        }
+        info!(
+            "Laser on for {} frames every {} frames, from frame {}",
+            flicker.duration, flicker.period, flicker.phase
+        );
+    }
+}
+
+/// Set the phase of `flicker` from the `target` picked among the `candidates` to strike.
+/// Returns the frame it strikes the replay at, and where the replay is then.
+fn aim(flicker: &mut Flicker, candidates: &[(u32, Vec3)], target: usize) -> Option<(u32, Vec3)> {
+    if candidates.is_empty() {
+        let steps = (target % flicker.period as usize) as u32;
+        flicker.phase = steps * PHASE_STEP % flicker.period;
+        return None;
+    }
+    let (frame, position) = candidates[target % candidates.len()];
+    flicker.phase = frame % flicker.period;
+    Some((frame, position))
+}
+
+/// Frames where the replay is inside of the laser, with where it is then.
+fn strike_candidates(
+    collider: &Collider,
+    position: Vec2,
+    recorded_positions: &RecordedPositions,
+) -> Vec<(u32, Vec3)> {
+    recorded_positions
+        .positions
+        .iter()
+        .filter(|(_, p, _)| {
+            collider.contains_point(Position(position), Rotation::default(), p.truncate())
+        })
+        .map(|(frame, p, _)| (*frame, *p))
+        .collect()
+}
+
+pub struct LaserPlugin;
+
+impl Plugin for LaserPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            Self::flicker_on_frames.run_if(
+                (in_state(GameMode::Replay).or(in_state(GameMode::Survive)))
+                    .and(in_state(GameState::Game)),
+            ),
+        )
+        .add_systems(
+            FixedUpdate,
+            Self::force_disable.run_if(
+                (in_state(GameMode::Defend).or(on_event::<PlayerDeath>))
+                    .and(in_state(GameState::Game)),
+            ),
+        )
+        .add_systems(
+            Update,
+            (Self::tune_ghost_laser, Self::preview_strike)
+                .chain()
+                .run_if(in_state(GameMode::Defend).and(in_state(GameState::Game))),
+        )
+        .add_systems(OnExit(GameMode::Defend), Self::remove_strike_label)
+        .add_systems(FixedUpdate, Self::follow_laser);
+    }
+}
+
+impl LaserPlugin {
+    fn force_disable(
+        mut commands: Commands,
+        query: Query<(Entity, &mut Visibility), With<Flicker>>,
+    ) {
+        for (entity, mut visibility) in query {
+            commands.entity(entity).insert(ColliderDisabled);
+            *visibility = Visibility::Hidden;
+        }
+    }
+    fn flicker_on_frames(
+        mut commands: Commands,
+        tick: Res<SimulationTick>,
+        recorded_positions: Res<RecordedPositions>,
+        query: Query<(Entity, &Flicker, &mut Visibility, Has<ColliderDisabled>)>,
+        mut sfx_writer: EventWriter<PlaySfx>,
+    ) {
+        let start_frame = recorded_positions.frame_start;
+        for (entity, flicker, mut visibility, is_disabled) in query {
+            let active = tick
+                .0
+                .checked_sub(start_frame)
+                .is_some_and(|frame| flicker.is_active_at(frame));
+            if active != is_disabled {
+                // Already in the right state
+                continue;
+            }
+            if active {
+                sfx_writer.write(PlaySfx(SfxKind::Laser));
+                commands.entity(entity).remove::<ColliderDisabled>();
+                *visibility = Visibility::Visible;
+            } else {
+                commands.entity(entity).insert(ColliderDisabled);
+                *visibility = Visibility::Hidden;
+            }
+        }
+    }
+
+    /// Change the timing of the laser being placed. Scrolling, or the gamepad bumpers, pick
+    /// another frame to strike at. Holding shift or control while scrolling, or the gamepad face
+    /// buttons and triggers, change the period and the duration.
+    fn tune_ghost_laser(
+        scroll: Res<AccumulatedMouseScroll>,
+        keyboard: Res<ButtonInput<KeyCode>>,
+        gamepads: Query<&Gamepad>,
+        ghost: Single<(&mut LaserTarget, &mut Flicker), With<GhostObstacle>>,
+    ) {
+        let (mut target, mut flicker) = ghost.into_inner();
+        let pressed = |button| gamepads.iter().any(|gamepad| gamepad.just_pressed(button));
+        let steps = |up, down| pressed(up) as i32 - pressed(down) as i32;
+        // Not `signum`, which is 1 when there's no scrolling at all
+        let scroll_steps = (scroll.delta.y > 0.) as i32 - (scroll.delta.y < 0.) as i32;
+
+        let (mut target_steps, mut period_steps, mut duration_steps) = (
+            steps(GamepadButton::RightTrigger, GamepadButton::LeftTrigger),
+            // The d-pad moves the laser, like the left stick
+            steps(GamepadButton::East, GamepadButton::West),
+            steps(GamepadButton::RightTrigger2, GamepadButton::LeftTrigger2),
+        );
+        if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            period_steps += scroll_steps;
+        } else if keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+            duration_steps += scroll_steps;
+        } else {
+            target_steps += scroll_steps;
+        }
+
+        if target_steps != 0 {
+            target.0 = target.0.wrapping_add_signed(target_steps as isize);
+        }
+        if period_steps != 0 {
+            flicker.change_period(period_steps);
+        }
+        if duration_steps != 0 {
+            flicker.change_duration(duration_steps);
+        }
+    }
+
+    /// Highlight when the laser being placed is on along the replay's path, where it would hit the
+    /// replay, and the frame it's timed for.
+    fn preview_strike(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        ghost: Option<
+            Single<(&Transform, &Collider, &mut Flicker, &LaserTarget), With<GhostObstacle>>,
+        >,
+        label: Option<
+            Single<
+                (Entity, &mut Text2d, &mut Transform),
+                (With<StrikeLabel>, Without<GhostObstacle>),
+            >,
+        >,
+        recorded_positions: Res<RecordedPositions>,
+        mut gizmos: Gizmos,
+    ) {
+        let Some(ghost) = ghost else {
+            if let Some(label) = label {
+                commands.entity(label.0).despawn();
+            }
+            return;
+        };
+        let (transform, collider, mut flicker, target) = ghost.into_inner();
+        let candidates = strike_candidates(
+            collider,
+            transform.translation.truncate(),
+            &recorded_positions,
+        );
+        let strike = aim(&mut flicker, &candidates, target.0);
+
+        // When the laser is on along the replay's path
+        for pair in recorded_positions.positions.windows(2) {
+            let [(frame, start, _), (_, end, _)] = pair else {
+                continue;
+            };
+            let color = if flicker.is_active_at(*frame) {
+                Color::srgb(1.0, 0.2, 0.3)
+            } else {
+                Color::srgba(1.0, 1.0, 1.0, 0.2)
+            };
+            gizmos.line_2d(start.truncate(), end.truncate(), color);
+        }
+        for (frame, position) in &candidates {
+            let (radius, color) = if flicker.is_active_at(*frame) {
+                (6., Color::srgb(1.0, 0.2, 0.3))
+            } else {
+                (3., Color::srgba(1.0, 1.0, 1.0, 0.4))
+            };
+            gizmos.circle_2d(position.truncate(), radius, color);
+        }
+
+        let timing = format!(
+            "On for {} frames (max {}) every {} frames",
+            flicker.duration,
+            flicker.max_duration(),
+            flicker.period
+        );
+        let (text, anchor) = match strike {
+            Some((strike_frame, strike_position)) => {
+                gizmos.circle_2d(strike_position.truncate(), 24., Color::srgb(1.0, 0.2, 0.3));
+                let index = target.0 % candidates.len();
+                (
+                    format!(
+                        "Strikes at frame {strike_frame} ({}/{})\n{timing}",
+                        index + 1,
+                        candidates.len()
+                    ),
+                    strike_position,
+                )
+            }
+            None => (
+                format!("First on at frame {}\n{timing}", flicker.phase),
+                transform.translation,
+            ),
+        };
+        let translation = anchor + vec3(0., 50., 2.);
+        match label {
+            Some(label) => {
+                let (_, mut label_text, mut transform) = label.into_inner();
+                label_text.0 = text;
+                transform.translation = translation;
+            }
+            None => {
+                commands.spawn((
+                    StrikeLabel,
+                    Text2d::new(text),
+                    TextFont {
+                        font_size: 20.,
+                        font: asset_server.load("fonts/capitolcity.ttf"),
+                        ..Default::default()
+                    },
+                    Transform::from_translation(translation),
+                ));
+            }
+        }
+    }
+
+    fn remove_strike_label(mut commands: Commands, label: Query<Entity, With<StrikeLabel>>) {
+        for entity in label {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    /// The shadow stays where its laser is, even while the laser is off.
+    fn follow_laser(
+        lasers: Query<(&Transform, &Companions), (With<Flicker>, Without<FakeLaser>)>,
+        mut shadows: Query<&mut Transform, With<FakeLaser>>,
+    ) {
+        for (laser, companions) in &lasers {
+            let mut shadows = shadows.iter_many_mut(&companions.0);
+            while let Some(mut shadow) = shadows.fetch_next() {
+                shadow.translation = laser.translation.truncate().extend(0.);
+            }
+        }
+    }
+}
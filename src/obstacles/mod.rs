@@ -1,70 +1,158 @@
+//! Obstacles the player places to stop their replay.
+//!
+//! Every kind of obstacle implements [`Obstacle`] in its own module, and is registered in the
+//! [`ObstacleRegistry`] under its [`ObstacleType`]. The placement lifecycle (ghost following the
+//! mouse, clicking to place it) and killing the player are handled here for all of them.
+
+use std::collections::HashMap;
+
 use crate::{
     GameState,
+    audio::{PlaySfx, SfxKind},
+    input::{Action, InputMap, gamepad_direction, gamepad_just_pressed},
     modes::GameMode,
-    player::{Player, PlayerDeath, record_position::RecordedPositions},
+    player::{
+        Player, PlayerDeath,
+        past_runs::{PastRun, Stopped},
+    },
 };
 use avian2d::prelude::*;
-use bevy::{
-    diagnostic::FrameCount, ecs::entity_disabling::Disabled,
-    input::common_conditions::input_just_pressed, prelude::*, sprite::AlphaMode2d,
-    window::PrimaryWindow,
-};
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Component)]
+pub mod laser;
+pub mod saw;
+pub mod shop;
+pub mod spike;
+
+pub use laser::{FakeLaser, Flicker};
+
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ObstacleType {
     Spike,
     Laser,
+    Saw,
+}
+
+impl ObstacleType {
+    pub const ALL: [ObstacleType; 3] =
+        [ObstacleType::Spike, ObstacleType::Laser, ObstacleType::Saw];
 }
 
 /// Marker component for obstacles
 #[derive(Debug, Component)]
 pub struct ObstacleMarker;
 
-/// Marker component for the shadow of a laser
-#[derive(Debug, Component)]
-pub struct FakeLaser;
-
 /// mark the last inserted obstacle to allow the player to move it
 #[derive(Debug, Component)]
 pub struct LastInsertedObstacle;
 
+/// Entities spawned along with an obstacle by [`Obstacle::spawn`], such as the shadow of a laser.
+/// They follow the obstacle around, and restarting despawns them along with it.
+#[derive(Debug, Component, Default)]
+pub struct Companions(pub Vec<Entity>);
+
 /// Ghost obstacle, for an obstacle that's not placed yet.
 #[derive(Debug, Component)]
 pub struct GhostObstacle;
 
-#[derive(Debug, Component)]
-pub struct Flicker {
-    /// How many frames between each appearance start, in frames
-    period: u32,
-    /// How long until this appears for the first time, in frames
-    delay: u32,
-    /// How long this appears for, in frames
-    duration: u32,
-    /// first frame to strike
-    strike_frame: u32,
-}
+/// Where the ghost obstacle goes when it's moved with a gamepad stick instead of the mouse.
+/// Moving the mouse gives control back to it.
+#[derive(Debug, Resource, Default)]
+struct GamepadCursor(Option<Vec2>);
+
+/// How fast the gamepad moves the ghost obstacle, in world units per second
+const GAMEPAD_CURSOR_SPEED: f32 = 600.;
+
+/// Where the player clicked so far while placing the ghost obstacle.
+#[derive(Debug, Component, Default)]
+pub struct PlacementPoints(pub Vec<Vec2>);
 
 #[derive(Debug, Event)]
 pub struct SpawnGhostObstacleEvent {
     obs_type: ObstacleType,
 }
 
-#[derive(Debug, Event)]
-pub struct EmitLaserPositionEvent {
-    position: Vec2,
+impl SpawnGhostObstacleEvent {
+    pub fn new(obs_type: ObstacleType) -> Self {
+        Self { obs_type }
+    }
 }
 
-impl SpawnGhostObstacleEvent {
-    // TODO: make it actually random
-    pub fn random() -> Self {
-        let random = rand::random_range(0.0..1.0);
-        Self {
-            obs_type: if random < 0.35 {
-                ObstacleType::Laser
-            } else {
-                ObstacleType::Spike
-            },
-        }
+/// What's needed to spawn the entities of an obstacle.
+pub struct ObstacleSpawner<'a, 'w, 's> {
+    pub commands: &'a mut Commands<'w, 's>,
+    pub meshes: &'a mut Assets<Mesh>,
+    pub materials: &'a mut Assets<ColorMaterial>,
+}
+
+/// Behaviour of a kind of obstacle.
+pub trait Obstacle: Send + Sync + 'static {
+    /// Insert the components specific to this kind of obstacle into `obstacle`, which already has
+    /// its [`ObstacleType`], [`Transform`] and material.
+    /// Returns any other entity spawned along with it, so they can be cleaned up together.
+    fn spawn(&self, spawner: &mut ObstacleSpawner, obstacle: Entity, position: Vec2)
+    -> Vec<Entity>;
+
+    /// Draw an outline of the obstacle, used while placing it and in the editor.
+    fn draw_preview(&self, gizmos: &mut Gizmos, position: Vec2, color: Color);
+
+    /// Where the ghost should go when the cursor is at `target`.
+    fn constrain_placement(&self, target: Vec2) -> Vec2 {
+        target
+    }
+
+    /// How many clicks it takes to place the obstacle. Each of them is stored in its
+    /// [`PlacementPoints`], and [`Obstacle::on_placed`] is called after the last one.
+    fn placement_points(&self) -> usize {
+        1
+    }
+
+    /// How many build points it takes to buy the obstacle in the shop.
+    fn cost(&self) -> u32 {
+        1
+    }
+
+    /// Whether touching the obstacle kills the player.
+    fn is_lethal(&self) -> bool {
+        true
+    }
+
+    /// Sound played when the obstacle kills the player, on top of the death sound.
+    fn hit_sfx(&self) -> Option<SfxKind> {
+        None
+    }
+
+    /// Called once the obstacle has been placed, to set up anything that depends on the replay,
+    /// such as timing.
+    fn on_placed(&self, _world: &mut World, _entity: Entity) {}
+}
+
+/// Every kind of obstacle, by its type.
+#[derive(Resource, Default)]
+pub struct ObstacleRegistry(HashMap<ObstacleType, Box<dyn Obstacle>>);
+
+impl ObstacleRegistry {
+    pub fn get(&self, obs_type: ObstacleType) -> &dyn Obstacle {
+        self.0
+            .get(&obs_type)
+            .unwrap_or_else(|| panic!("obstacle {obs_type:?} was not registered"))
+            .as_ref()
+    }
+}
+
+pub trait RegisterObstacle {
+    fn register_obstacle(&mut self, obs_type: ObstacleType, obstacle: impl Obstacle) -> &mut Self;
+}
+
+impl RegisterObstacle for App {
+    fn register_obstacle(&mut self, obs_type: ObstacleType, obstacle: impl Obstacle) -> &mut Self {
+        self.init_resource::<ObstacleRegistry>();
+        self.world_mut()
+            .resource_mut::<ObstacleRegistry>()
+            .0
+            .insert(obs_type, Box::new(obstacle));
+        self
     }
 }
 
@@ -72,13 +160,19 @@ pub struct ObstaclePlugin;
 
 impl Plugin for ObstaclePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<SpawnGhostObstacleEvent>()
-            .add_event::<EmitLaserPositionEvent>()
+        app.add_plugins((laser::LaserPlugin, saw::SawPlugin, shop::ShopPlugin))
+            .register_obstacle(ObstacleType::Spike, spike::Spike)
+            .register_obstacle(ObstacleType::Laser, laser::Laser)
+            .register_obstacle(ObstacleType::Saw, saw::Saw)
+            .add_event::<SpawnGhostObstacleEvent>()
+            .init_resource::<GamepadCursor>()
             .add_systems(
                 Update,
                 (
                     Self::spawn_obstacle_ghost.run_if(on_event::<SpawnGhostObstacleEvent>),
+                    Self::move_gamepad_cursor,
                     Self::ghost_obstacle_follow_mouse,
+                    Self::draw_ghost_preview,
                 )
                     .run_if(in_state(GameState::Game)),
             )
@@ -86,277 +180,90 @@ impl Plugin for ObstaclePlugin {
                 FixedPreUpdate,
                 Self::place_ghost_obs
                     .run_if(
-                        input_just_pressed(MouseButton::Left)
-                            .and(in_state(GameMode::Defend).and(in_state(GameState::Game))),
+                        (input_just_pressed(MouseButton::Left)
+                            .or(gamepad_just_pressed(GamepadButton::South)))
+                        .and(in_state(GameMode::Defend).and(in_state(GameState::Game)))
+                        .and(not(pointer_over_ui)),
                     )
                     .before(crate::update_state),
-            )
-            .add_systems(
-                FixedUpdate,
-                Self::flicker_on_frames.run_if(
-                    (in_state(GameMode::Replay).or(in_state(GameMode::Survive)))
-                        .and(in_state(GameState::Game)),
-                ),
-            )
-            .add_systems(
-                FixedUpdate,
-                Self::force_disable.run_if(
-                    (in_state(GameMode::Defend).or(on_event::<PlayerDeath>))
-                        .and(in_state(GameState::Game)),
-                ),
-            )
-            .add_systems(FixedPreUpdate, Self::emit_real_laser_pos)
-            .add_systems(
-                FixedUpdate,
-                Self::follow_real_laser_pos.run_if(on_event::<EmitLaserPositionEvent>),
             );
     }
 }
 
 impl ObstaclePlugin {
-    fn force_disable(
-        mut commands: Commands,
-        query: Query<(Entity, &mut Visibility), With<Flicker>>,
-    ) {
-        for (entity, mut visibility) in query {
-            commands.entity(entity).insert(ColliderDisabled);
-            *visibility = Visibility::Hidden;
-        }
-    }
-    fn flicker_on_frames(
+    fn spawn_obstacle_ghost(
         mut commands: Commands,
-        frame_counter: Res<FrameCount>,
-        recorded_positions: Res<RecordedPositions>,
-        query: Query<(
-            Entity,
-            &mut Transform,
-            &mut Flicker,
-            &mut Visibility,
-            Has<ColliderDisabled>,
-        )>,
-        asset_server: Res<AssetServer>,
+        mut obstacle_event: EventReader<SpawnGhostObstacleEvent>,
+        window: Option<Single<&Window, With<PrimaryWindow>>>,
+        camera: Option<Single<(&Camera, &GlobalTransform, &Transform), With<Camera2d>>>,
+        mut meshes: ResMut<Assets<Mesh>>,
+        mut materials: ResMut<Assets<ColorMaterial>>,
+        registry: Res<ObstacleRegistry>,
     ) {
-        let start_frame = recorded_positions.frame_start;
-        for (entity, transform, mut flicker, mut visibility, is_disabled) in query {
-            let frame_for_flicker = frame_counter.0 - start_frame;
-            if frame_counter.0 < start_frame || frame_for_flicker < flicker.delay {
-                commands.entity(entity).insert(ColliderDisabled);
-                *visibility = Visibility::Hidden;
-                return;
-            }
-            if ((frame_for_flicker + flicker.delay) % flicker.period) < flicker.duration
-                || (frame_for_flicker == flicker.delay && flicker.strike_frame < flicker.period)
-            {
-                if !is_disabled {
-                    continue;
-                }
-                commands.spawn(AudioPlayer::new(asset_server.load("sounds/laser.wav")));
-                commands.entity(entity).remove::<ColliderDisabled>();
-                *visibility = Visibility::Visible;
-            } else {
-                if !is_disabled {
-                    commands.entity(entity).insert(ColliderDisabled);
-                    *visibility = Visibility::Hidden;
-                }
+        // Without a window (e.g. when simulating the game in tests), it's spawned at the origin
+        let cursor_pos = match (window, camera) {
+            (Some(window), Some(camera)) => {
+                let (camera, camera_global_transform, camera_transform) = camera.into_inner();
+                get_cursor_world_pos(window.into_inner(), camera, camera_global_transform)
+                    .unwrap_or(camera_transform.translation.xy())
             }
+            _ => Vec2::ZERO,
+        };
+        let mut spawner = ObstacleSpawner {
+            commands: &mut commands,
+            meshes: &mut meshes,
+            materials: &mut materials,
+        };
+        for event in obstacle_event.read() {
+            let obstacle = spawn_obstacle(&mut spawner, &registry, event.obs_type, cursor_pos, ());
+            spawner
+                .commands
+                .entity(obstacle)
+                .insert((GhostObstacle, PlacementPoints::default()));
         }
     }
-
-    fn emit_real_laser_pos(
-        query: Query<(&Transform, &ObstacleType)>,
-        mut laser_pos_event: EventWriter<EmitLaserPositionEvent>,
+    /// Move the gamepad cursor with the left stick, starting from where the ghost obstacle is.
+    fn move_gamepad_cursor(
+        mut gamepad_cursor: ResMut<GamepadCursor>,
+        mut mouse_moved: EventReader<CursorMoved>,
+        gamepads: Query<&Gamepad>,
+        ghost_obs: Single<&Transform, With<GhostObstacle>>,
+        time: Res<Time>,
     ) {
-        for (transform, obstacle_type) in query {
-            match obstacle_type {
-                ObstacleType::Laser => {
-                    laser_pos_event.write(EmitLaserPositionEvent {
-                        position: transform.translation.truncate(),
-                    });
-                }
-                _ => {
-                    // do nothing
-                }
-            }
+        if mouse_moved.read().count() > 0 {
+            gamepad_cursor.0 = None;
         }
-    }
-
-    fn follow_real_laser_pos(
-        mut laser_pos_event: EventReader<EmitLaserPositionEvent>,
-        query: Query<&mut Transform, With<FakeLaser>>,
-    ) {
-        for mut transform in query {
-            transform.translation = laser_pos_event.read().next().map_or(
-                transform.translation,
-                |EmitLaserPositionEvent { position }| position.extend(0.),
-            );
+        let direction = gamepad_direction(&gamepads);
+        if direction == Vec2::ZERO {
+            return;
         }
+        let from = gamepad_cursor.0.unwrap_or(ghost_obs.translation.truncate());
+        gamepad_cursor.0 = Some(from + direction * GAMEPAD_CURSOR_SPEED * time.delta_secs());
     }
 
-    fn spawn_obstacle_ghost(
-        mut commands: Commands,
-        mut obstacle_event: EventReader<SpawnGhostObstacleEvent>,
-        window: Single<&Window, With<PrimaryWindow>>,
-        camera: Single<(&Camera, &GlobalTransform, &Transform), With<Camera2d>>,
-        mut meshes: ResMut<Assets<Mesh>>,
-        mut materials: ResMut<Assets<ColorMaterial>>,
-        positions: ResMut<RecordedPositions>,
-    ) {
-        let (camera, camera_global_transform, camera_transform) = camera.into_inner();
-
-        let cursor_pos = get_cursor_world_pos(window.into_inner(), camera, camera_global_transform)
-            .unwrap_or(camera_transform.translation.xy());
-        for event in obstacle_event.read() {
-            // Components that all obstacles have in common
-            let common_components = (
-                GhostObstacle,
-                ObstacleMarker,
-                Transform::from_translation(cursor_pos.extend(0.)),
-                MeshMaterial2d(
-                    materials.add(ColorMaterial::from_color(Color::srgb(1.0, 0.2, 0.3))),
-                ),
-            );
-
-            match event.obs_type {
-                ObstacleType::Spike => {
-                    commands
-                        .spawn((
-                            common_components,
-                            CollisionEventsEnabled,
-                            Sensor,
-                            Collider::triangle(vec2(-14.0, 0.0), vec2(14.0, 0.0), vec2(0.0, 28.0)),
-                            Mesh2d(meshes.add(Triangle2d::new(
-                                vec2(-20.0, 0.0),
-                                vec2(20.0, 0.0),
-                                vec2(0.0, 40.0),
-                            ))),
-                            ObstacleType::Spike,
-                        ))
-                        .observe(
-                            |trigger: Trigger<OnCollisionStart>,
-                             player_query: Query<(), With<Player>>,
-                             mut death_writer: EventWriter<PlayerDeath>,
-                             ghost_query: Query<&GhostObstacle>,
-                             previous_last_obstacle: Option<
-                                Single<Entity, With<LastInsertedObstacle>>,
-                            >,
-                             game_mode: Res<State<GameMode>>| {
-                                let spike = trigger.target();
-                                // If we're still placing the spike
-                                if ghost_query.contains(spike) {
-                                    return;
-                                }
-
-                                match game_mode.get() {
-                                    GameMode::Replay => {
-                                        // If we're hitting someone that wasn't the previous last obstacle,
-                                        // we should ignore that collision
-                                        if !(previous_last_obstacle.map_or(true, |obs| {
-                                            obs.into_inner() == trigger.target()
-                                        })) {
-                                            return;
-                                        }
-                                    }
-
-                                    _ => {
-                                        // do nothing
-                                    }
-                                }
-
-                                if player_query.contains(trigger.collider) {
-                                    death_writer.write(PlayerDeath);
-                                }
-                            },
-                        );
-                }
-                ObstacleType::Laser => {
-                    commands.spawn((
-                        Mesh2d(meshes.add(Rectangle {
-                            half_size: vec2(20., 1000.),
-                        })),
-                        MeshMaterial2d(materials.add(ColorMaterial {
-                            color: Color::srgb(1.0, 0.2, 0.3).with_alpha(0.2),
-                            alpha_mode: AlphaMode2d::Blend,
-                            ..default()
-                        })),
-                        Transform::from_translation(cursor_pos.extend(0.)),
-                        FakeLaser,
-                    ));
-                    commands
-                        .spawn((
-                            common_components,
-                            ObstacleType::Laser,
-                            CollisionEventsEnabled,
-                            Sensor,
-                            Collider::rectangle(30.0, 1000.0),
-                            Mesh2d(meshes.add(Rectangle {
-                                half_size: vec2(20., 10_000.),
-                            })),
-                            Flicker {
-                                period: 120,
-                                delay: 120,
-                                duration: 20,
-                                strike_frame: 10000,
-                            },
-                        ))
-                        .observe(
-                            |trigger: Trigger<OnCollisionStart>,
-                             player_query: Query<(), With<Player>>,
-                             mut death_writer: EventWriter<PlayerDeath>,
-                             ghost_query: Query<&GhostObstacle>,
-                             previous_last_obstacle: Option<
-                                Single<Entity, With<LastInsertedObstacle>>,
-                            >,
-                             game_mode: Res<State<GameMode>>| {
-                                let laser = trigger.target();
-                                // If we're still placing the laser
-                                if ghost_query.contains(laser) {
-                                    return;
-                                }
-
-                                match game_mode.get() {
-                                    GameMode::Replay => {
-                                        // If we're hitting someone that wasn't the previous last obstacle,
-                                        // we should ignore that collision
-                                        if !(previous_last_obstacle.map_or(true, |obs| {
-                                            obs.into_inner() == trigger.target()
-                                        })) {
-                                            return;
-                                        }
-                                    }
-
-                                    _ => {
-                                        // do nothing
-                                    }
-                                }
-
-                                if player_query.contains(trigger.collider) {
-                                    death_writer.write(PlayerDeath);
-                                }
-                            },
-                        );
-                }
-            }
-        }
-    }
-    /// Make the ghost obstacle follow the mouse.
+    /// Make the ghost obstacle follow the mouse, or the gamepad cursor if it's being used.
     /// Similar to the camera tracking to the player, it lags a bit behind.
     fn ghost_obstacle_follow_mouse(
         window: Single<&Window, With<PrimaryWindow>>,
         ghost_obs: Single<(&mut Transform, &ObstacleType), With<GhostObstacle>>,
         camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+        registry: Res<ObstacleRegistry>,
+        gamepad_cursor: Res<GamepadCursor>,
     ) {
         let (mut obs_transform, obs_type) = ghost_obs.into_inner();
         let (camera, camera_transform) = camera.into_inner();
 
         let inner_window = window.into_inner();
 
-        let mut target_translation = get_cursor_world_pos(inner_window, camera, camera_transform)
+        let target_translation = gamepad_cursor
+            .0
+            .or_else(|| get_cursor_world_pos(inner_window, camera, camera_transform))
             .map_or(obs_transform.translation, |pos| {
-                pos.extend(obs_transform.translation.z)
+                registry
+                    .get(*obs_type)
+                    .constrain_placement(pos)
+                    .extend(obs_transform.translation.z)
             });
-        if matches!(obs_type, ObstacleType::Laser) {
-            target_translation = target_translation.with_y(0.)
-        }
 
         let diff = target_translation - obs_transform.translation;
         let diff_length = diff.length();
@@ -369,18 +276,47 @@ impl ObstaclePlugin {
         obs_transform.translation += dir * f32::min(50., diff_length);
     }
 
+    fn draw_ghost_preview(
+        ghost_obs: Single<(&Transform, &ObstacleType), With<GhostObstacle>>,
+        registry: Res<ObstacleRegistry>,
+        mut gizmos: Gizmos,
+    ) {
+        let (transform, obs_type) = ghost_obs.into_inner();
+        registry.get(*obs_type).draw_preview(
+            &mut gizmos,
+            transform.translation.truncate(),
+            Color::srgba(1.0, 1.0, 1.0, 0.4),
+        );
+    }
+
     fn place_ghost_obs(
         mut commands: Commands,
-        ghost_obs: Single<Entity, With<GhostObstacle>>,
+        ghost_obs: Single<
+            (Entity, &ObstacleType, &Transform, &mut PlacementPoints),
+            With<GhostObstacle>,
+        >,
         previous_last_obstacle: Option<Single<Entity, With<LastInsertedObstacle>>>,
-
-        mut flicker_query: Query<(&mut Flicker, &Position, &Rotation, &Collider)>,
-
-        positions: Res<RecordedPositions>,
         asset_server: Res<AssetServer>,
+        registry: Res<ObstacleRegistry>,
+        input_map: Res<InputMap>,
     ) {
+        let (entity, obs_type, transform, mut points) = ghost_obs.into_inner();
+        let obs_type = *obs_type;
+        points.0.push(transform.translation.truncate());
+        let needed = registry.get(obs_type).placement_points();
+        if points.0.len() < needed {
+            info!(
+                "Placement point {} of {needed} for the ghost obstacle",
+                points.0.len()
+            );
+            return;
+        }
+
         commands.spawn((
-            Text::new("Press [SPACE] to continue..."),
+            Text::new(format!(
+                "Press [{}] or [SELECT] to continue...",
+                input_map.describe(Action::Continue)
+            )),
             SpaceToContinueMarker,
             TextFont {
                 font_size: 30.,
@@ -394,38 +330,81 @@ impl ObstaclePlugin {
                 .entity(obs.into_inner())
                 .remove::<LastInsertedObstacle>();
         }
-        let entity = ghost_obs.into_inner();
+        commands.queue(move |world: &mut World| {
+            world.resource_scope(|world, registry: Mut<ObstacleRegistry>| {
+                registry.get(obs_type).on_placed(world, entity);
+            });
+        });
+
         let mut obs_entity = commands.entity(entity);
+        obs_entity.remove::<(GhostObstacle, PlacementPoints)>();
+        obs_entity.insert(LastInsertedObstacle);
+    }
+}
 
-        if let Ok((mut flicker, pos, rot, col)) = flicker_query.get_mut(entity) {
-            let position_where_player_is_in_laser = positions
-                .positions
-                .iter()
-                .filter(|(_, p, _)| col.contains_point(*pos, *rot, p.truncate()))
-                .collect::<Vec<_>>();
-            let period = flicker.period;
-            let delay = position_where_player_is_in_laser
-                .get(if position_where_player_is_in_laser.len() != 0 {
-                    rand::random_range(0..position_where_player_is_in_laser.len())
-                } else {
-                    1
-                })
-                .map_or(120, |(frame, _p, _)| {
-                    flicker.strike_frame = *frame;
-                    info!("Will strike player in frame {frame}, when they're in position {_p}");
-                    if *frame > period {
-                        frame.next_multiple_of(period) - frame
-                    } else {
-                        *frame
-                    }
-                });
-            flicker.delay = delay;
+/// Clicks on the UI, such as the replay timeline, aren't meant to place obstacles.
+fn pointer_over_ui(interactions: Query<&Interaction>) -> bool {
+    interactions
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+}
+
+/// Kill the player when they touch the obstacle this observes, or stop the past run that touched it.
+fn kill_player_on_contact(
+    trigger: Trigger<OnCollisionStart>,
+    mut commands: Commands,
+    player_query: Query<(), With<Player>>,
+    past_run_query: Query<(), With<PastRun>>,
+    mut death_writer: EventWriter<PlayerDeath>,
+    mut sfx_writer: EventWriter<PlaySfx>,
+    ghost_query: Query<&GhostObstacle>,
+    obstacle_types: Query<&ObstacleType>,
+    registry: Res<ObstacleRegistry>,
+    previous_last_obstacle: Option<Single<Entity, With<LastInsertedObstacle>>>,
+    game_mode: Res<State<GameMode>>,
+) {
+    let obstacle = trigger.target();
+    // If we're still placing the obstacle
+    if ghost_query.contains(obstacle) {
+        return;
+    }
+    let hit_sfx = obstacle_types
+        .get(obstacle)
+        .ok()
+        .and_then(|obs_type| registry.get(*obs_type).hit_sfx());
+
+    // Any obstacle stops a past run, they weren't recorded against the newer ones
+    if past_run_query.contains(trigger.collider) {
+        commands.entity(trigger.collider).insert(Stopped);
+        sfx_writer.write(PlaySfx(SfxKind::Death));
+        if let Some(sfx) = hit_sfx {
+            sfx_writer.write(PlaySfx(sfx));
         }
+        return;
+    }
 
-        obs_entity.remove::<GhostObstacle>();
-        obs_entity.insert(LastInsertedObstacle);
+    match game_mode.get() {
+        GameMode::Replay => {
+            // If we're hitting someone that wasn't the previous last obstacle,
+            // we should ignore that collision
+            if !(previous_last_obstacle.map_or(true, |obs| obs.into_inner() == obstacle)) {
+                return;
+            }
+        }
+
+        _ => {
+            // do nothing
+        }
+    }
+
+    if player_query.contains(trigger.collider) {
+        death_writer.write(PlayerDeath);
+        if let Some(sfx) = hit_sfx {
+            sfx_writer.write(PlaySfx(sfx));
+        }
     }
 }
+
 #[derive(Component)]
 pub struct SpaceToContinueMarker;
 pub fn get_cursor_world_pos(
@@ -441,3 +420,48 @@ pub fn get_cursor_world_pos(
         .cursor_position()
         .map(|pos| camera.viewport_to_world_2d(camera_transform, pos).unwrap())
 }
+
+/// Spawn an already placed obstacle of the given type at `position`, returning its entity.
+/// `extra` is added to every entity that is spawned for it (the obstacle and, for lasers, their shadow).
+pub fn spawn_obstacle(
+    spawner: &mut ObstacleSpawner,
+    registry: &ObstacleRegistry,
+    obs_type: ObstacleType,
+    position: Vec2,
+    extra: impl Bundle + Clone,
+) -> Entity {
+    // Components that all obstacles have in common
+    let entity = spawner
+        .commands
+        .spawn((
+            ObstacleMarker,
+            obs_type,
+            Transform::from_translation(position.extend(0.)),
+            MeshMaterial2d(
+                spawner
+                    .materials
+                    .add(ColorMaterial::from_color(Color::srgb(1.0, 0.2, 0.3))),
+            ),
+            extra.clone(),
+        ))
+        .id();
+
+    let obstacle = registry.get(obs_type);
+    let companions = obstacle.spawn(spawner, entity, position);
+    for &companion in &companions {
+        spawner.commands.entity(companion).insert(extra.clone());
+    }
+    spawner
+        .commands
+        .entity(entity)
+        .insert(Companions(companions));
+    if obstacle.is_lethal() {
+        spawner
+            .commands
+            .entity(entity)
+            .insert(CollisionEventsEnabled)
+            .observe(kill_player_on_contact);
+    }
+
+    entity
+}
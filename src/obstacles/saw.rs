@@ -0,0 +1,178 @@
+//! A saw blade going back and forth between two points.
+
+use avian2d::prelude::*;
+use bevy::{input::mouse::AccumulatedMouseScroll, prelude::*};
+
+use crate::{
+    GameState, SimulationTick,
+    modes::GameMode,
+    obstacles::{GhostObstacle, Obstacle, ObstacleSpawner, PlacementPoints},
+    player::record_position::RecordedPositions,
+};
+
+const SAW_RADIUS: f32 = 20.;
+/// Speed of a saw when it's placed, in world units per tick
+pub const DEFAULT_SAW_SPEED: f32 = 3.;
+pub const MIN_SAW_SPEED: f32 = 1.;
+pub const MAX_SAW_SPEED: f32 = 10.;
+
+pub struct Saw;
+
+/// Path a saw travels along, from `start` to `end` and back.
+#[derive(Debug, Component)]
+pub struct Patrol {
+    pub start: Vec2,
+    pub end: Vec2,
+    /// In world units per tick
+    pub speed: f32,
+}
+
+impl Patrol {
+    /// Where the saw is after `frames` frames, and how far it travelled to get there.
+    /// Only depends on the frame, so the saw is always in the same place at the same point of a replay.
+    fn position_at(&self, frames: u32) -> (Vec2, f32) {
+        let travelled = frames as f32 * self.speed;
+        let length = self.start.distance(self.end);
+        if length <= f32::EPSILON {
+            return (self.start, travelled);
+        }
+        let along = travelled % (2. * length);
+        let along = if along > length {
+            2. * length - along
+        } else {
+            along
+        };
+        (self.start.lerp(self.end, along / length), travelled)
+    }
+}
+
+impl Obstacle for Saw {
+    fn spawn(
+        &self,
+        spawner: &mut ObstacleSpawner,
+        obstacle: Entity,
+        position: Vec2,
+    ) -> Vec<Entity> {
+        spawner.commands.entity(obstacle).insert((
+            Sensor,
+            // A bit smaller than the mesh, so barely touching the teeth is forgiven
+            Collider::circle(SAW_RADIUS * 0.8),
+            Mesh2d(spawner.meshes.add(RegularPolygon::new(SAW_RADIUS, 8))),
+            Patrol {
+                start: position,
+                end: position,
+                speed: DEFAULT_SAW_SPEED,
+            },
+        ));
+        Vec::new()
+    }
+
+    fn draw_preview(&self, gizmos: &mut Gizmos, position: Vec2, color: Color) {
+        gizmos.circle_2d(position, SAW_RADIUS, color);
+    }
+
+    fn cost(&self) -> u32 {
+        2
+    }
+
+    /// The first click is where the saw starts, and the second where it turns around.
+    fn placement_points(&self) -> usize {
+        2
+    }
+
+    fn on_placed(&self, world: &mut World, entity: Entity) {
+        let Some(points) = world.get::<PlacementPoints>(entity) else {
+            return;
+        };
+        let (start, end) = (points.0[0], points.0[points.0.len() - 1]);
+        let Some(mut patrol) = world.get_mut::<Patrol>(entity) else {
+            return;
+        };
+        patrol.start = start;
+        patrol.end = end;
+        info!(
+            "Saw will patrol from {start} to {end} at {} units/tick",
+            patrol.speed
+        );
+        if let Some(mut transform) = world.get_mut::<Transform>(entity) {
+            transform.translation = start.extend(transform.translation.z);
+        }
+    }
+}
+
+pub struct SawPlugin;
+
+impl Plugin for SawPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            Self::patrol.run_if(
+                (in_state(GameMode::Replay).or(in_state(GameMode::Survive)))
+                    .and(in_state(GameState::Game)),
+            ),
+        )
+        .add_systems(
+            FixedUpdate,
+            Self::back_to_start.run_if(in_state(GameMode::Defend).and(in_state(GameState::Game))),
+        )
+        .add_systems(
+            Update,
+            (Self::change_ghost_speed, Self::draw_paths)
+                .run_if(in_state(GameMode::Defend).and(in_state(GameState::Game))),
+        );
+    }
+}
+
+impl SawPlugin {
+    fn patrol(
+        tick: Res<SimulationTick>,
+        recorded_positions: Res<RecordedPositions>,
+        query: Query<(&Patrol, &mut Transform), Without<GhostObstacle>>,
+    ) {
+        let frames = tick.0.saturating_sub(recorded_positions.frame_start);
+        for (patrol, mut transform) in query {
+            let (position, travelled) = patrol.position_at(frames);
+            transform.translation = position.extend(transform.translation.z);
+            transform.rotation = Quat::from_rotation_z(-travelled / SAW_RADIUS);
+        }
+    }
+
+    /// Saws wait at the start of their path while the next obstacle is placed
+    fn back_to_start(query: Query<(&Patrol, &mut Transform), Without<GhostObstacle>>) {
+        for (patrol, mut transform) in query {
+            transform.translation = patrol.start.extend(transform.translation.z);
+            transform.rotation = Quat::IDENTITY;
+        }
+    }
+
+    /// Scrolling while placing a saw changes how fast it goes.
+    fn change_ghost_speed(
+        scroll: Res<AccumulatedMouseScroll>,
+        ghost: Single<&mut Patrol, With<GhostObstacle>>,
+    ) {
+        if scroll.delta.y == 0. {
+            return;
+        }
+        let mut patrol = ghost.into_inner();
+        patrol.speed =
+            (patrol.speed + scroll.delta.y.signum() * 0.5).clamp(MIN_SAW_SPEED, MAX_SAW_SPEED);
+        info!("Saw speed set to {} units/tick", patrol.speed);
+    }
+
+    fn draw_paths(
+        saws: Query<&Patrol, Without<GhostObstacle>>,
+        ghost: Option<Single<(&Transform, &PlacementPoints), (With<Patrol>, With<GhostObstacle>)>>,
+        mut gizmos: Gizmos,
+    ) {
+        let color = Color::srgba(1.0, 0.2, 0.3, 0.4);
+        for patrol in &saws {
+            gizmos.line_2d(patrol.start, patrol.end, color);
+        }
+        if let Some(ghost) = ghost {
+            let (transform, points) = ghost.into_inner();
+            if let Some(start) = points.0.first() {
+                gizmos.line_2d(*start, transform.translation.truncate(), color);
+            }
+        }
+    }
+}
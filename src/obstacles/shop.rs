@@ -0,0 +1,272 @@
+//! Shop where the defender spends the points earned by beating the level on the next obstacle.
+//!
+//! Each loop in survive mode is worth [`POINTS_PER_LOOP`], and each kind of obstacle costs
+//! [`Obstacle::cost`](super::Obstacle::cost). Points that aren't spent are kept for the next loops,
+//! to save up for the more expensive obstacles.
+//! In the daily challenge, there's no shop: the obstacles are handed out in an order that's the same
+//! for everyone playing it.
+
+use bevy::prelude::*;
+
+use crate::{
+    GameState,
+    audio::{PlaySfx, SfxKind},
+    daily::DailyChallenge,
+    menu::{HOVERED_BUTTON, NORMAL_BUTTON, TEXT_COLOR},
+    modes::{GameMode, GoalReached, RestartLoop, RestartRun},
+    obstacles::{GhostObstacle, ObstacleRegistry, ObstacleType, SpawnGhostObstacleEvent},
+    rng::GameRng,
+};
+
+/// Points earned each time the level is beaten, enough for the cheapest obstacle.
+pub const POINTS_PER_LOOP: u32 = 2;
+
+const UNAFFORDABLE_TEXT_COLOR: Color = Color::srgb(0.4, 0.4, 0.4);
+
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BuildPoints>()
+            .init_resource::<LoopStartPoints>()
+            .init_resource::<ShopSelection>()
+            .add_systems(
+                FixedPreUpdate,
+                (
+                    Self::earn_points
+                        .run_if(on_event::<GoalReached>.and(in_state(GameMode::Survive))),
+                    Self::reset_points.run_if(on_event::<RestartRun>),
+                    Self::refund_loop
+                        .run_if(on_event::<RestartLoop>.and(not(in_state(GameMode::Survive)))),
+                )
+                    .before(crate::update_state),
+            )
+            .add_systems(OnEnter(GameMode::Defend), Self::open_shop)
+            .add_systems(OnExit(GameMode::Defend), Self::close_shop)
+            .add_systems(
+                Update,
+                (Self::select_item, Self::buy, Self::update_shop)
+                    .chain()
+                    .run_if(in_state(GameMode::Defend).and(in_state(GameState::Game))),
+            );
+    }
+}
+
+/// Points left to buy obstacles with.
+#[derive(Debug, Resource, Default)]
+pub struct BuildPoints(pub u32);
+
+/// Build points before the current loop earned any.
+#[derive(Debug, Resource, Default)]
+struct LoopStartPoints(u32);
+
+/// Index of the item picked with the gamepad, in [`ObstacleType::ALL`]
+#[derive(Debug, Resource, Default)]
+struct ShopSelection(usize);
+
+#[derive(Component)]
+struct ShopMarker;
+
+/// Button buying an obstacle of this type.
+#[derive(Debug, Component)]
+struct ShopItem(ObstacleType);
+
+#[derive(Component)]
+struct BuildPointsLabel;
+
+impl ShopPlugin {
+    fn earn_points(mut points: ResMut<BuildPoints>, mut loop_start: ResMut<LoopStartPoints>) {
+        loop_start.0 = points.0;
+        points.0 += POINTS_PER_LOOP;
+        info!(
+            "Earned {POINTS_PER_LOOP} build points, {} to spend",
+            points.0
+        );
+    }
+
+    fn reset_points(mut points: ResMut<BuildPoints>) {
+        points.0 = 0;
+    }
+
+    /// Restarting the loop takes back the points it earned, and gives back what they bought.
+    fn refund_loop(mut points: ResMut<BuildPoints>, loop_start: Res<LoopStartPoints>) {
+        points.0 = loop_start.0;
+    }
+
+    /// Open the shop, unless the last obstacle is being moved again because the replay got past it.
+    fn open_shop(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        registry: Res<ObstacleRegistry>,
+        ghost: Option<Single<(), With<GhostObstacle>>>,
+        mut selection: ResMut<ShopSelection>,
+        daily: Res<DailyChallenge>,
+        mut rng: ResMut<GameRng>,
+        mut spawn_obstacle_writer: EventWriter<SpawnGhostObstacleEvent>,
+    ) {
+        if ghost.is_some() {
+            return;
+        }
+        if daily.is_active() {
+            let obs_type = ObstacleType::ALL[rng.random_range(0..ObstacleType::ALL.len())];
+            info!("Daily challenge hands out a {obs_type:?}");
+            spawn_obstacle_writer.write(SpawnGhostObstacleEvent::new(obs_type));
+            return;
+        }
+        selection.0 = 0;
+
+        let text_font = TextFont {
+            font_size: 24.,
+            font: asset_server.load("fonts/capitolcity.ttf"),
+            ..Default::default()
+        };
+        commands
+            .spawn((
+                ShopMarker,
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(60.),
+                    width: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.),
+                    ..Default::default()
+                },
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    BuildPointsLabel,
+                    Text::default(),
+                    text_font.clone(),
+                    TextColor(TEXT_COLOR),
+                ));
+                parent
+                    .spawn(Node {
+                        column_gap: Val::Px(10.),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        for (index, obs_type) in ObstacleType::ALL.into_iter().enumerate() {
+                            parent.spawn((
+                                Button,
+                                ShopItem(obs_type),
+                                Node {
+                                    width: Val::Px(200.),
+                                    height: Val::Px(50.),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..Default::default()
+                                },
+                                BackgroundColor(NORMAL_BUTTON),
+                                children![(
+                                    Text::new(format!(
+                                        "[{}] {obs_type:?}: {}",
+                                        index + 1,
+                                        registry.get(obs_type).cost()
+                                    )),
+                                    text_font.clone(),
+                                    TextColor(TEXT_COLOR),
+                                )],
+                            ));
+                        }
+                    });
+            });
+    }
+
+    fn close_shop(mut commands: Commands, query: Query<Entity, With<ShopMarker>>) {
+        for entity in query {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    /// The gamepad bumpers move between the items.
+    fn select_item(gamepads: Query<&Gamepad>, mut selection: ResMut<ShopSelection>) {
+        let pressed = |button| gamepads.iter().any(|gamepad| gamepad.just_pressed(button));
+        let count = ObstacleType::ALL.len();
+        if pressed(GamepadButton::RightTrigger) {
+            selection.0 = (selection.0 + 1) % count;
+        } else if pressed(GamepadButton::LeftTrigger) {
+            selection.0 = (selection.0 + count - 1) % count;
+        }
+    }
+
+    /// Buy the item that was clicked, whose number key was pressed, or that's selected with the
+    /// gamepad when pressing south.
+    fn buy(
+        mut commands: Commands,
+        shop: Option<Single<Entity, With<ShopMarker>>>,
+        items: Query<(&Interaction, &ShopItem), Changed<Interaction>>,
+        keyboard: Res<ButtonInput<KeyCode>>,
+        gamepads: Query<&Gamepad>,
+        selection: Res<ShopSelection>,
+        registry: Res<ObstacleRegistry>,
+        mut points: ResMut<BuildPoints>,
+        mut spawn_obstacle_writer: EventWriter<SpawnGhostObstacleEvent>,
+        mut sfx_writer: EventWriter<PlaySfx>,
+    ) {
+        let Some(shop) = shop else {
+            return;
+        };
+        const KEYS: [KeyCode; 3] = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3];
+        let clicked = items
+            .iter()
+            .find(|(interaction, _)| **interaction == Interaction::Pressed)
+            .map(|(_, item)| item.0);
+        let from_keyboard = ObstacleType::ALL
+            .into_iter()
+            .zip(KEYS)
+            .find(|(_, key)| keyboard.just_pressed(*key))
+            .map(|(obs_type, _)| obs_type);
+        let from_gamepad = gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(GamepadButton::South))
+            .then(|| ObstacleType::ALL[selection.0]);
+        let Some(obs_type) = clicked.or(from_keyboard).or(from_gamepad) else {
+            return;
+        };
+
+        let cost = registry.get(obs_type).cost();
+        if cost > points.0 {
+            info!(
+                "Can't afford a {obs_type:?}: it costs {cost}, {} left",
+                points.0
+            );
+            return;
+        }
+        points.0 -= cost;
+        info!("Bought a {obs_type:?} for {cost}, {} left", points.0);
+        sfx_writer.write(PlaySfx(SfxKind::ButtonSelect));
+        spawn_obstacle_writer.write(SpawnGhostObstacleEvent::new(obs_type));
+        commands.entity(shop.into_inner()).despawn();
+    }
+
+    fn update_shop(
+        points: Res<BuildPoints>,
+        selection: Res<ShopSelection>,
+        registry: Res<ObstacleRegistry>,
+        label: Option<Single<&mut Text, With<BuildPointsLabel>>>,
+        items: Query<(&ShopItem, &Interaction, &mut BackgroundColor, &Children)>,
+        mut texts: Query<&mut TextColor>,
+    ) {
+        if let Some(mut label) = label {
+            label.0 = format!("Pick an obstacle: {} points to spend", points.0);
+        }
+        for (item, interaction, mut background_color, children) in items {
+            let selected = ObstacleType::ALL[selection.0] == item.0;
+            *background_color = if selected || *interaction != Interaction::None {
+                HOVERED_BUTTON.into()
+            } else {
+                NORMAL_BUTTON.into()
+            };
+            let affordable = registry.get(item.0).cost() <= points.0;
+            let mut text_color = texts.iter_many_mut(children);
+            while let Some(mut text_color) = text_color.fetch_next() {
+                text_color.0 = if affordable {
+                    TEXT_COLOR
+                } else {
+                    UNAFFORDABLE_TEXT_COLOR
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! A spike sitting on the ground, always deadly.
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    audio::SfxKind,
+    obstacles::{Obstacle, ObstacleSpawner},
+};
+
+pub struct Spike;
+
+impl Obstacle for Spike {
+    fn spawn(
+        &self,
+        spawner: &mut ObstacleSpawner,
+        obstacle: Entity,
+        _position: Vec2,
+    ) -> Vec<Entity> {
+        spawner.commands.entity(obstacle).insert((
+            Sensor,
+            // A bit smaller than the mesh, so barely touching the spike is forgiven
+            Collider::triangle(vec2(-14.0, 0.0), vec2(14.0, 0.0), vec2(0.0, 28.0)),
+            Mesh2d(spawner.meshes.add(Triangle2d::new(
+                vec2(-20.0, 0.0),
+                vec2(20.0, 0.0),
+                vec2(0.0, 40.0),
+            ))),
+        ));
+        Vec::new()
+    }
+
+    fn draw_preview(&self, gizmos: &mut Gizmos, position: Vec2, color: Color) {
+        gizmos.linestrip_2d(
+            [
+                position + vec2(-20.0, 0.0),
+                position + vec2(0.0, 40.0),
+                position + vec2(20.0, 0.0),
+                position + vec2(-20.0, 0.0),
+            ],
+            color,
+        );
+    }
+
+    fn hit_sfx(&self) -> Option<SfxKind> {
+        Some(SfxKind::Spike)
+    }
+}
@@ -3,21 +3,43 @@ use bevy::prelude::*;
 
 use crate::{
     GameState, LevelDimensions,
+    audio::{PlaySfx, SfxKind},
     environment::ResetEnvironment,
+    level::Level,
     modes::GameMode,
     player::{
         movement::{CharacterControllerBundle, MovementAction},
+        past_runs::{PastRun, PastRuns, Stopped},
+        record_movement::RecordedMovements,
         record_position::RecordedPositions,
     },
+    settings::Settings,
 };
 
 /// Player died
 #[derive(Debug, Event)]
 pub struct PlayerDeath;
 
+/// Size of the player's character, in world units.
+pub const PLAYER_SIZE: Vec2 = vec2(40., 40.);
+/// See `MovementAcceleration`.
+pub const PLAYER_ACCELERATION: f32 = 6250.;
+/// Horizontal speed kept from one tick to the next.
+pub const PLAYER_DAMPING: f32 = 0.82;
+/// See `JumpImpulse`.
+pub const PLAYER_JUMP_IMPULSE: f32 = 1600.;
+/// Share of the jump impulse added every tick the jump is held while going up.
+pub const JUMP_HOLD_BOOST: f32 = 0.05;
+/// See [`GravityScale`].
+pub const PLAYER_GRAVITY_SCALE: f32 = 8.;
+/// How far below the bottom of the level a character falls before dying, in tiles.
+const FALL_DEPTH: i32 = 4;
+
 mod movement;
-//pub mod record_movement;
+pub mod past_runs;
+pub mod record_movement;
 pub mod record_position;
+pub mod replay_file;
 /// Player spawning and movement handling.
 pub struct PlayerPlugin;
 
@@ -25,15 +47,33 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
             movement::PlayerMovementPlugin,
+            past_runs::PastRunsPlugin,
+            record_movement::RecordMovementPlugin,
             record_position::RecordPositionPlugin,
+            replay_file::ReplayFilePlugin,
         ))
         .add_event::<ResetEnvironment>()
         .add_event::<PlayerDeath>()
-        .add_systems(OnExit(GameState::Menu), Self::spawn_player)
+        .add_systems(
+            OnTransition {
+                exited: GameState::Menu,
+                entered: GameState::Game,
+            },
+            Self::spawn_player,
+        )
         .add_systems(
             FixedUpdate,
             Self::move_to_start_pos.run_if(on_event::<ResetEnvironment>),
         )
+        .add_systems(
+            FixedUpdate,
+            Self::fall_out_of_level
+                .after(Self::move_to_start_pos)
+                .run_if(
+                    (in_state(GameMode::Survive).or(in_state(GameMode::Replay)))
+                        .and(in_state(GameState::Game)),
+                ),
+        )
         .add_systems(
             FixedPreUpdate,
             (Self::handle_death)
@@ -52,40 +92,73 @@ impl PlayerPlugin {
         mut meshes: ResMut<Assets<Mesh>>,
         mut materials: ResMut<Assets<ColorMaterial>>,
         level_dimensions: Res<LevelDimensions>,
+        level: Level,
     ) {
         commands.spawn((
+            Player,
+            Self::character(
+                &mut meshes,
+                &mut materials,
+                Color::WHITE,
+                level_dimensions
+                    .grid_pos_to_pixels(level.player_start(), PLAYER_SIZE)
+                    .extend(1.),
+            ),
+        ));
+    }
+
+    /// Everything a character moving like the player needs, whether it's controlled by the player
+    /// or replaying a run.
+    fn character(
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<ColorMaterial>,
+        color: Color,
+        translation: Vec3,
+    ) -> impl Bundle {
+        (
             // Appearance
             Mesh2d(meshes.add(Rectangle {
-                half_size: vec2(20., 20.),
+                half_size: PLAYER_SIZE / 2.,
             })),
-            MeshMaterial2d(materials.add(ColorMaterial::from_color(Color::WHITE))),
+            MeshMaterial2d(materials.add(ColorMaterial::from_color(color))),
             // Movement
-            CharacterControllerBundle::new(Collider::rectangle(40., 40.))
-                .with_movement(6250., 0.82, 1600.),
+            CharacterControllerBundle::new(Collider::rectangle(PLAYER_SIZE.x, PLAYER_SIZE.y))
+                .with_movement(PLAYER_ACCELERATION, PLAYER_DAMPING, PLAYER_JUMP_IMPULSE),
             Friction::ZERO.with_combine_rule(CoefficientCombine::Min),
             Restitution::ZERO.with_combine_rule(CoefficientCombine::Min),
             ColliderDensity(2.0),
-            GravityScale(8.0),
-            Transform::from_translation(
-                level_dimensions
-                    .grid_pos_to_pixels((1, 3), vec2(40., 40.))
-                    .extend(1.),
-            ),
-        ));
+            GravityScale(PLAYER_GRAVITY_SCALE),
+            Transform::from_translation(translation),
+        )
     }
 
     fn move_to_start_pos(
         player: Single<(&mut Transform, &mut LinearVelocity), With<Player>>,
         level_dimensions: Res<LevelDimensions>,
+        level: Level,
         mut recorded_positions: ResMut<RecordedPositions>,
+        mut recorded_movements: ResMut<RecordedMovements>,
     ) {
         let (mut transform, mut velocity) = player.into_inner();
         transform.translation = level_dimensions
-            .grid_pos_to_pixels((1, 3), vec2(40., 40.))
+            .grid_pos_to_pixels(level.player_start(), PLAYER_SIZE)
             .extend(1.);
 
         velocity.0 = Vec2::ZERO;
         recorded_positions.locked = false;
+        recorded_movements.locked = false;
+    }
+
+    /// Falling through a gap in the ground is as deadly as an obstacle.
+    fn fall_out_of_level(
+        player: Single<&Transform, (With<Player>, Without<Stopped>)>,
+        level_dimensions: Res<LevelDimensions>,
+        mut death_writer: EventWriter<PlayerDeath>,
+    ) {
+        let (_, y) = level_dimensions.pixels_to_grid_pos(player.translation.truncate());
+        if y < -FALL_DEPTH {
+            death_writer.write(PlayerDeath);
+        }
     }
 
     fn handle_death(
@@ -94,22 +167,45 @@ impl PlayerPlugin {
         mut reset_environment: EventWriter<ResetEnvironment>,
 
         mut recorded_positions: ResMut<RecordedPositions>,
+        mut recorded_movements: ResMut<RecordedMovements>,
 
-        asset_server: Res<AssetServer>,
+        mut sfx_writer: EventWriter<PlaySfx>,
         mut commands: Commands,
+
+        player: Single<(Entity, Has<Stopped>), With<Player>>,
+        running_past_runs: Query<(), (With<PastRun>, Without<Stopped>)>,
+        mut past_runs: ResMut<PastRuns>,
+        settings: Res<Settings>,
     ) {
-        commands.spawn(AudioPlayer::new(asset_server.load("sounds/death.wav")));
+        let (player, already_stopped) = player.into_inner();
+        if !already_stopped {
+            sfx_writer.write(PlaySfx(SfxKind::Death));
+        }
         match game_mode.get() {
             GameMode::Survive => {
                 info!("Player died in survive mode. Restarting mode.");
                 recorded_positions.positions.clear();
                 recorded_positions.locked = true;
+                recorded_movements.clear();
+                recorded_movements.locked = true;
             }
             GameMode::Replay => {
+                if !running_past_runs.is_empty() {
+                    info!(
+                        "Player stopped in replay mode, waiting for the past runs to be stopped."
+                    );
+                    commands.entity(player).insert(Stopped);
+                    return;
+                }
                 info!("Player died in replay mode. Moving on to survive.");
+                if settings.replay_all_runs {
+                    past_runs.0.push(recorded_movements.inputs.clone());
+                }
                 state.set(GameMode::Survive);
                 recorded_positions.positions.clear();
                 recorded_positions.locked = true;
+                recorded_movements.clear();
+                recorded_movements.locked = true;
             }
             GameMode::Defend => {
                 warn!(
@@ -9,8 +9,13 @@ use bevy::prelude::*;
 
 use crate::{
     GameState,
+    audio::{PlaySfx, SfxKind},
+    input::{Action, ActionInput, any_gamepad_pressed, gamepad_direction},
     modes::GameMode,
-    player::{Player, record_position::RecordPositionPlugin},
+    player::{
+        JUMP_HOLD_BOOST, Player, past_runs::PastRunsPlugin, record_movement::RecordMovementPlugin,
+        record_position::RecordPositionPlugin,
+    },
 };
 
 pub struct PlayerMovementPlugin;
@@ -20,17 +25,26 @@ impl Plugin for PlayerMovementPlugin {
         app.add_systems(
             FixedUpdate,
             (
-                ((
-                    Self::keyboard_input, // Only get the input if we're in the survive mode
-                    Self::update_grounded,
-                    Self::movement,
-                    Self::apply_movement_damping,
+                // Only get the input if we're in the survive mode
+                (Self::keyboard_input, RecordMovementPlugin::record_movement)
+                    .chain()
+                    .run_if(in_state(GameMode::Survive)),
+                // And feed the recorded one back on the replay
+                (
+                    RecordMovementPlugin::play_recorded_movement,
+                    RecordPositionPlugin::advance_replay_frame,
                 )
-                    .run_if(in_state(GameMode::Survive)))
-                .chain(),
-                RecordPositionPlugin::play_recorded_position.run_if(in_state(GameMode::Replay)),
+                    .run_if(in_state(GameMode::Replay)),
+                Self::update_grounded,
+                Self::movement,
+                PastRunsPlugin::drive_past_runs.run_if(in_state(GameMode::Replay)),
+                Self::apply_movement_damping,
             )
-                .run_if(in_state(GameState::Game)),
+                .chain()
+                .run_if(
+                    (in_state(GameMode::Survive).or(in_state(GameMode::Replay)))
+                        .and(in_state(GameState::Game)),
+                ),
         )
         .add_event::<MovementAction>()
         .add_event::<ActualJump>();
@@ -59,12 +73,20 @@ pub struct MovementDampingFactor(Scalar);
 #[derive(Component, Debug)]
 pub struct JumpImpulse(pub Scalar);
 
+/// Characters collide with the world, but go through each other.
+#[derive(PhysicsLayer, Default)]
+enum CollisionLayer {
+    #[default]
+    World,
+    Character,
+}
+
 #[derive(Debug, Bundle)]
 pub struct CharacterControllerBundle {
-    player: Player,
     movement: MovementBundle,
     body: RigidBody,
     collider: Collider,
+    layers: CollisionLayers,
     locked_axes: LockedAxes,
     ground_caster: ShapeCaster,
 }
@@ -102,11 +124,13 @@ impl CharacterControllerBundle {
         caster_shape.set_scale(Vector::ONE * 0.99, 10);
 
         Self {
-            player: Player,
             body: RigidBody::Dynamic,
             collider,
+            layers: CollisionLayers::new(CollisionLayer::Character, CollisionLayer::World),
             ground_caster: ShapeCaster::new(caster_shape, Vector::ZERO, 0.0, Dir2::NEG_Y)
-                .with_max_distance(1.0),
+                .with_max_distance(1.0)
+                // Standing on another character doesn't count as being grounded
+                .with_query_filter(SpatialQueryFilter::from_mask(CollisionLayer::World)),
             locked_axes: LockedAxes::ROTATION_LOCKED,
             movement: MovementBundle::default(),
         }
@@ -126,19 +150,25 @@ impl CharacterControllerBundle {
 impl PlayerMovementPlugin {
     pub(crate) fn keyboard_input(
         mut movement_event_writer: EventWriter<MovementAction>,
-        keyboard: Res<ButtonInput<KeyCode>>,
+        input: ActionInput,
+        gamepads: Query<&Gamepad>,
     ) {
-        let left = keyboard.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]);
-        let right = keyboard.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]);
+        let left = input.pressed(Action::MoveLeft);
+        let right = input.pressed(Action::MoveRight);
 
         let horizontal = right as i8 - left as i8;
-        let direction = horizontal as Scalar;
+        // The keyboard wins over the gamepad, whose stick can go anywhere between -1 and 1
+        let direction = if horizontal != 0 {
+            horizontal as Scalar
+        } else {
+            gamepad_direction(&gamepads).x as Scalar
+        };
 
         if direction != 0.0 {
             movement_event_writer.write(MovementAction::Move(direction));
         }
 
-        if keyboard.pressed(KeyCode::Space) {
+        if input.pressed(Action::Jump) || any_gamepad_pressed(&gamepads, GamepadButton::South) {
             movement_event_writer.write(MovementAction::Jump);
         }
     }
@@ -146,7 +176,7 @@ impl PlayerMovementPlugin {
     /// Updates the [`Grounded`] status for character controllers.
     fn update_grounded(
         mut commands: Commands,
-        mut query: Query<(Entity, &ShapeHits), With<Player>>,
+        mut query: Query<(Entity, &ShapeHits), With<MovementAcceleration>>,
     ) {
         for (entity, hits) in &mut query {
             // The character is grounded if the shape caster has a hit
@@ -161,18 +191,20 @@ impl PlayerMovementPlugin {
     }
 
     fn movement(
-        mut commands: Commands,
-        asset_server: Res<AssetServer>,
+        mut sfx_writer: EventWriter<PlaySfx>,
         mut jump_writer: EventWriter<ActualJump>,
 
         time: Res<Time>,
         mut movement_event_reader: EventReader<MovementAction>,
-        mut controllers: Query<(
-            &MovementAcceleration,
-            &JumpImpulse,
-            &mut LinearVelocity,
-            Has<Grounded>,
-        )>,
+        mut controllers: Query<
+            (
+                &MovementAcceleration,
+                &JumpImpulse,
+                &mut LinearVelocity,
+                Has<Grounded>,
+            ),
+            With<Player>,
+        >,
     ) {
         // Precision is adjusted so that the example works with
         // both the `f32` and `f64` features. Otherwise you don't need this.
@@ -182,20 +214,52 @@ impl PlayerMovementPlugin {
             for (movement_acceleration, jump_impulse, mut linear_velocity, is_grounded) in
                 &mut controllers
             {
+                let jumped = Self::apply_action(
+                    *event,
+                    movement_acceleration,
+                    jump_impulse,
+                    &mut linear_velocity,
+                    is_grounded,
+                    delta_time,
+                );
                 match event {
-                    MovementAction::Move(direction) => {
-                        linear_velocity.x += *direction * movement_acceleration.0 * delta_time;
+                    // Only one footstep plays at a time, which paces them
+                    MovementAction::Move(direction) if is_grounded && *direction != 0. => {
+                        sfx_writer.write(PlaySfx(SfxKind::Walk));
+                    }
+                    MovementAction::Jump if jumped => {
+                        sfx_writer.write(PlaySfx(SfxKind::Jump));
+                        jump_writer.write(ActualJump);
                     }
-                    MovementAction::Jump => {
-                        if is_grounded {
-                            linear_velocity.y = jump_impulse.0;
-                            commands
-                                .spawn((AudioPlayer::new(asset_server.load("sounds/jump.wav")),));
-                            jump_writer.write(ActualJump);
-                        } else if linear_velocity.y > 0.0 {
-                            linear_velocity.y += jump_impulse.0 * 0.05;
-                        }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Apply a movement action to a character. Returns whether it jumped off the ground.
+    pub(super) fn apply_action(
+        action: MovementAction,
+        movement_acceleration: &MovementAcceleration,
+        jump_impulse: &JumpImpulse,
+        linear_velocity: &mut LinearVelocity,
+        is_grounded: bool,
+        delta_time: Scalar,
+    ) -> bool {
+        match action {
+            MovementAction::Move(direction) => {
+                linear_velocity.x += direction * movement_acceleration.0 * delta_time;
+                false
+            }
+            MovementAction::Jump => {
+                if is_grounded {
+                    linear_velocity.y = jump_impulse.0;
+                    true
+                } else {
+                    if linear_velocity.y > 0.0 {
+                        linear_velocity.y += jump_impulse.0 * JUMP_HOLD_BOOST;
                     }
+                    false
                 }
             }
         }
@@ -0,0 +1,190 @@
+//! Replay every previous winning run at the same time as the latest one, when
+//! [`Settings::replay_all_runs`](crate::settings::Settings::replay_all_runs) is on.
+//!
+//! Each past run is its own character, re-simulated from its recorded inputs like the player is.
+//! The defenses only hold once all of them, and the player, have been stopped.
+
+use avian2d::{math::AdjustPrecision, prelude::*};
+use bevy::prelude::*;
+
+use crate::{
+    GameState, LevelDimensions,
+    level::Level,
+    modes::GameMode,
+    player::{
+        Player, PlayerDeath, PlayerPlugin,
+        movement::{
+            Grounded, JumpImpulse, MovementAcceleration, MovementAction, PlayerMovementPlugin,
+        },
+        record_movement::{REPLAY_GRACE_TICKS, TickInput},
+    },
+    settings::Settings,
+};
+
+pub struct PastRunsPlugin;
+
+impl Plugin for PastRunsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PastRuns>()
+            .add_systems(OnEnter(GameMode::Replay), Self::spawn_past_runs)
+            .add_systems(
+                OnExit(GameMode::Replay),
+                (Self::despawn_past_runs, Self::resume_player),
+            )
+            .add_systems(FixedUpdate, Self::park_stopped)
+            .add_systems(
+                FixedPreUpdate,
+                Self::end_replay_once_all_stopped
+                    .before(PlayerPlugin::handle_death)
+                    .before(crate::update_state)
+                    .run_if(in_state(GameMode::Replay).and(in_state(GameState::Game))),
+            );
+    }
+}
+
+/// Inputs of every winning run that was replayed and stopped, oldest first.
+#[derive(Debug, Resource, Default)]
+pub struct PastRuns(pub(crate) Vec<Vec<TickInput>>);
+
+/// A character replaying one of the [`PastRuns`].
+#[derive(Debug, Component)]
+pub struct PastRun {
+    /// Index of the run in [`PastRuns`]
+    run: usize,
+    next_tick: usize,
+    /// How many ticks have gone by since the run's inputs ran out
+    ticks_past_end: u32,
+}
+
+/// A character of the replay that was stopped, waiting for the others to be stopped too.
+#[derive(Debug, Component)]
+pub struct Stopped;
+
+impl PastRunsPlugin {
+    fn spawn_past_runs(
+        mut commands: Commands,
+        mut meshes: ResMut<Assets<Mesh>>,
+        mut materials: ResMut<Assets<ColorMaterial>>,
+        level_dimensions: Res<LevelDimensions>,
+        level: Level,
+        past_runs: Res<PastRuns>,
+        settings: Res<Settings>,
+    ) {
+        if !settings.replay_all_runs {
+            return;
+        }
+        info!("Replaying {} past runs", past_runs.0.len());
+        let start = level_dimensions.grid_pos_to_pixels(level.player_start(), vec2(40., 40.));
+        for run in 0..past_runs.0.len() {
+            commands.spawn((
+                PastRun {
+                    run,
+                    next_tick: 0,
+                    ticks_past_end: 0,
+                },
+                // Behind the player
+                PlayerPlugin::character(
+                    &mut meshes,
+                    &mut materials,
+                    Color::srgba(1., 1., 1., 0.4),
+                    start.extend(0.5),
+                ),
+            ));
+        }
+    }
+
+    /// Feed each past run its recorded inputs for this tick.
+    pub(super) fn drive_past_runs(
+        mut commands: Commands,
+        time: Res<Time>,
+        past_runs: Res<PastRuns>,
+        query: Query<
+            (
+                Entity,
+                &mut PastRun,
+                &MovementAcceleration,
+                &JumpImpulse,
+                &mut LinearVelocity,
+                Has<Grounded>,
+            ),
+            Without<Stopped>,
+        >,
+    ) {
+        let delta_time = time.delta_secs_f64().adjust_precision();
+        for (entity, mut past_run, acceleration, jump_impulse, mut velocity, is_grounded) in query {
+            let Some(input) = past_runs
+                .0
+                .get(past_run.run)
+                .and_then(|inputs| inputs.get(past_run.next_tick))
+                .copied()
+            else {
+                past_run.ticks_past_end += 1;
+                if past_run.ticks_past_end == REPLAY_GRACE_TICKS {
+                    info!("Past run {} ran out of inputs", past_run.run);
+                    commands.entity(entity).insert(Stopped);
+                }
+                continue;
+            };
+            past_run.next_tick += 1;
+
+            let mut apply = |action| {
+                PlayerMovementPlugin::apply_action(
+                    action,
+                    acceleration,
+                    jump_impulse,
+                    &mut velocity,
+                    is_grounded,
+                    delta_time,
+                )
+            };
+            if input.direction != 0.0 {
+                apply(MovementAction::Move(input.direction));
+            }
+            if input.jump {
+                apply(MovementAction::Jump);
+            }
+        }
+    }
+
+    /// Take stopped characters out of the level until the replay is over.
+    fn park_stopped(
+        mut commands: Commands,
+        query: Query<(Entity, &mut Visibility, &mut LinearVelocity), Added<Stopped>>,
+    ) {
+        for (entity, mut visibility, mut velocity) in query {
+            *visibility = Visibility::Hidden;
+            velocity.0 = Vec2::ZERO;
+            commands
+                .entity(entity)
+                .insert((RigidBodyDisabled, ColliderDisabled));
+        }
+    }
+
+    /// The player was stopped first, so the replay ends with the last past run.
+    fn end_replay_once_all_stopped(
+        _player: Single<(), (With<Player>, With<Stopped>)>,
+        running: Query<(), (With<PastRun>, Without<Stopped>)>,
+        mut death_writer: EventWriter<PlayerDeath>,
+    ) {
+        if running.is_empty() {
+            death_writer.write(PlayerDeath);
+        }
+    }
+
+    fn despawn_past_runs(mut commands: Commands, query: Query<Entity, With<PastRun>>) {
+        for entity in query {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    fn resume_player(
+        mut commands: Commands,
+        player: Single<(Entity, &mut Visibility), (With<Player>, With<Stopped>)>,
+    ) {
+        let (entity, mut visibility) = player.into_inner();
+        *visibility = Visibility::Inherited;
+        commands
+            .entity(entity)
+            .remove::<(Stopped, RigidBodyDisabled, ColliderDisabled)>();
+    }
+}
@@ -0,0 +1,102 @@
+//! Record the player's inputs every tick, so the replay can be re-simulated through the physics
+//! instead of just teleporting the player around.
+
+use avian2d::math::Scalar;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::player::{PlayerDeath, movement::MovementAction};
+
+/// How many ticks to wait after the recorded inputs run out before considering the replay stopped.
+pub(super) const REPLAY_GRACE_TICKS: u32 = 60;
+
+pub struct RecordMovementPlugin;
+
+/// The inputs the player gave in a single fixed tick.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TickInput {
+    /// Horizontal direction, between -1 and 1
+    pub direction: Scalar,
+    pub jump: bool,
+}
+
+#[derive(Debug, Resource)]
+pub struct RecordedMovements {
+    /// One entry per fixed tick since the recording started
+    pub(crate) inputs: Vec<TickInput>,
+    /// Next tick to be played back on the replay
+    pub(crate) next_tick: usize,
+    /// How many ticks have gone by since the recorded inputs ran out
+    pub(crate) ticks_past_end: u32,
+    pub(crate) locked: bool,
+}
+
+impl RecordedMovements {
+    pub(crate) fn clear(&mut self) {
+        self.inputs.clear();
+        self.next_tick = 0;
+        self.ticks_past_end = 0;
+    }
+}
+
+impl Plugin for RecordMovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RecordedMovements {
+            inputs: Vec::with_capacity(1024),
+            next_tick: 0,
+            ticks_past_end: 0,
+            locked: false,
+        });
+    }
+}
+
+impl RecordMovementPlugin {
+    /// Store the movement actions sent this tick. Must run after the input is read.
+    pub fn record_movement(
+        mut movement_reader: EventReader<MovementAction>,
+        mut recorded_movements: ResMut<RecordedMovements>,
+    ) {
+        if recorded_movements.locked {
+            movement_reader.clear();
+            return;
+        }
+
+        let mut input = TickInput::default();
+        for action in movement_reader.read() {
+            match action {
+                MovementAction::Move(direction) => input.direction += direction,
+                MovementAction::Jump => input.jump = true,
+            }
+        }
+        recorded_movements.inputs.push(input);
+    }
+
+    /// Send the recorded movement actions for this tick, as if the player was pressing the keys.
+    pub fn play_recorded_movement(
+        mut recorded_movements: ResMut<RecordedMovements>,
+        mut movement_writer: EventWriter<MovementAction>,
+        mut death_writer: EventWriter<PlayerDeath>,
+    ) {
+        let Some(input) = recorded_movements
+            .inputs
+            .get(recorded_movements.next_tick)
+            .copied()
+        else {
+            // The replay didn't make it to the goal with the inputs it had, so it was stopped.
+            recorded_movements.ticks_past_end += 1;
+            if recorded_movements.ticks_past_end == REPLAY_GRACE_TICKS {
+                info!("Replay ran out of inputs without reaching the goal");
+                death_writer.write(PlayerDeath);
+            }
+            return;
+        };
+        recorded_movements.next_tick += 1;
+
+        if input.direction != 0.0 {
+            movement_writer.write(MovementAction::Move(input.direction));
+        }
+        if input.jump {
+            movement_writer.write(MovementAction::Jump);
+        }
+    }
+}
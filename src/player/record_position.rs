@@ -1,12 +1,11 @@
 use bevy::{
     app::{App, FixedUpdate, Plugin},
-    diagnostic::FrameCount,
     ecs::resource::Resource,
     prelude::*,
 };
 
 use crate::{
-    GameState,
+    GameState, SimulationTick,
     modes::GameMode,
     player::{Player, movement::ActualJump},
 };
@@ -15,7 +14,7 @@ pub struct RecordPositionPlugin;
 
 #[derive(Debug, Resource)]
 pub struct RecordedPositions {
-    /// Which frame the positions started being recorded in
+    /// Which [`SimulationTick`] the positions started being recorded in
     pub(crate) frame_start: u32,
     pub(crate) positions: Vec<(u32, Vec3, bool)>, // (Frame of the position, position, player jumped)
     pub(crate) last_played_frame: usize,
@@ -42,7 +41,7 @@ impl RecordPositionPlugin {
     pub fn record_position(
         position: Single<&Transform, With<Player>>,
         mut recorded_positions: ResMut<RecordedPositions>,
-        frame_counter: Res<FrameCount>,
+        tick: Res<SimulationTick>,
 
         mut jump_reader: EventReader<ActualJump>,
     ) {
@@ -51,10 +50,10 @@ impl RecordPositionPlugin {
         }
         // info!("Recorded position! `{:?}`", position);
         if recorded_positions.positions.is_empty() {
-            recorded_positions.frame_start = frame_counter.0;
+            recorded_positions.frame_start = tick.0;
             // recorded_positions.last_played_frame = 0;
         }
-        let frame_from_start = frame_counter.0 - recorded_positions.frame_start;
+        let frame_from_start = tick.0 - recorded_positions.frame_start;
         recorded_positions.positions.push((
             frame_from_start,
             position.translation,
@@ -62,33 +61,23 @@ impl RecordPositionPlugin {
         ))
     }
 
-    pub fn play_recorded_position(
+    /// Keep track of how far along the recorded path the replay is. The replay itself is
+    /// re-simulated from the recorded inputs, so this doesn't move the player.
+    pub fn advance_replay_frame(
         mut recorded_positions: ResMut<RecordedPositions>,
-        frame_counter: Res<FrameCount>,
-        mut player: Single<&mut Transform, With<Player>>,
-        mut commands: Commands,
-        asset_server: Res<AssetServer>,
+        tick: Res<SimulationTick>,
     ) {
+        let frame_from_start = tick.0 - recorded_positions.frame_start;
         let start_frame = recorded_positions.last_played_frame;
-        let mut last_played_frame = recorded_positions.last_played_frame;
 
-        for (frame, pos, jumped) in recorded_positions
+        if let Some((frame, _, _)) = recorded_positions
             .positions
             .iter()
             .skip_while(|pos| pos.0 < start_frame as u32)
+            .take_while(|pos| pos.0 <= frame_from_start)
+            .last()
         {
-            if (frame_counter.0 - recorded_positions.frame_start) < *frame {
-                break;
-            }
-            if *jumped {
-                commands.spawn((AudioPlayer::new(asset_server.load("sounds/jump.wav")),));
-            }
-
-            player.translation = *pos;
-
-            last_played_frame = *frame as usize;
+            recorded_positions.last_played_frame = *frame as usize;
         }
-
-        recorded_positions.last_played_frame = last_played_frame;
     }
 }
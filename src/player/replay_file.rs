@@ -0,0 +1,257 @@
+//! Save and load winning runs, so they can be archived and shared.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    LevelId,
+    modes::GameMode,
+    player::{
+        record_movement::{RecordedMovements, TickInput},
+        record_position::RecordedPositions,
+    },
+    rng::GameRng,
+    storage::{self, StorageError},
+};
+
+/// Bump this whenever the layout of [`ReplayFile`] changes.
+/// Version 2: positions are in world units instead of depending on the size of the window.
+/// Version 3: the seed of the run is saved with it.
+pub const REPLAY_FORMAT_VERSION: u32 = 3;
+/// Where the replays are saved, inside the data directory.
+const REPLAY_DIR: &str = "replays";
+
+pub struct ReplayFilePlugin;
+
+/// Save the current run to disk.
+#[derive(Debug, Event)]
+pub struct SaveReplay;
+
+/// Replace the current run with one loaded from disk.
+#[derive(Debug, Event)]
+pub struct LoadReplay(pub PathBuf);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub format_version: u32,
+    /// Version of the game the replay was recorded in
+    pub game_version: String,
+    pub level_id: String,
+    /// Fixed ticks per second the replay was recorded with
+    pub tick_rate: f64,
+    /// Seed of the [`GameRng`] during the run
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayFile {
+    pub header: ReplayHeader,
+    /// (Frame of the position, position, player jumped)
+    pub positions: Vec<(u32, Vec3, bool)>,
+    /// Inputs for every tick of the run
+    pub inputs: Vec<TickInput>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Storage(StorageError),
+    UnsupportedFormat {
+        found: u32,
+    },
+    /// Recorded with another fixed timestep, so it wouldn't play back in sync
+    WrongTickRate {
+        found: f64,
+        expected: f64,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Storage(err) => err.fmt(f),
+            ReplayError::UnsupportedFormat { found } => write!(
+                f,
+                "replay format version {found} is not supported (expected {REPLAY_FORMAT_VERSION})"
+            ),
+            ReplayError::WrongTickRate { found, expected } => write!(
+                f,
+                "replay was recorded at {found} ticks per second, the game runs at {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<StorageError> for ReplayError {
+    fn from(err: StorageError) -> Self {
+        ReplayError::Storage(err)
+    }
+}
+
+impl ReplayFile {
+    pub fn new(
+        level_id: &LevelId,
+        tick_rate: f64,
+        seed: u64,
+        positions: &RecordedPositions,
+        movements: &RecordedMovements,
+    ) -> Self {
+        Self {
+            header: ReplayHeader {
+                format_version: REPLAY_FORMAT_VERSION,
+                game_version: env!("CARGO_PKG_VERSION").to_string(),
+                level_id: level_id.0.clone(),
+                tick_rate,
+                seed,
+            },
+            positions: positions.positions.clone(),
+            inputs: movements.inputs.clone(),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        Ok(storage::save_ron(path, self)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let replay: ReplayFile = storage::load_ron(path)?;
+        if replay.header.format_version != REPLAY_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedFormat {
+                found: replay.header.format_version,
+            });
+        }
+        if replay.header.game_version != env!("CARGO_PKG_VERSION") {
+            warn!(
+                "Replay was recorded in version {} of the game, it might not play back the same",
+                replay.header.game_version
+            );
+        }
+        Ok(replay)
+    }
+
+    /// Whether the replay was recorded at `tick_rate` fixed ticks per second, since it's played
+    /// back tick for tick.
+    pub fn check_tick_rate(&self, tick_rate: f64) -> Result<(), ReplayError> {
+        if (self.header.tick_rate - tick_rate).abs() > 1e-6 {
+            return Err(ReplayError::WrongTickRate {
+                found: self.header.tick_rate,
+                expected: tick_rate,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The most recently saved replay, if there's any.
+pub fn latest_replay() -> Option<PathBuf> {
+    std::fs::read_dir(storage::data_path(REPLAY_DIR))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "ron"))
+        .max_by_key(|entry| entry.metadata().and_then(|meta| meta.modified()).ok())
+        .map(|entry| entry.path())
+}
+
+impl Plugin for ReplayFilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveReplay>()
+            .add_event::<LoadReplay>()
+            .add_systems(
+                Update,
+                (
+                    Self::save_replay.run_if(on_event::<SaveReplay>),
+                    Self::load_replay.run_if(on_event::<LoadReplay>),
+                ),
+            );
+    }
+}
+
+impl ReplayFilePlugin {
+    fn save_replay(
+        mut save_reader: EventReader<SaveReplay>,
+        game_mode: Res<State<GameMode>>,
+        level_id: Res<LevelId>,
+        fixed_time: Res<Time<Fixed>>,
+        rng: Res<GameRng>,
+        positions: Res<RecordedPositions>,
+        movements: Res<RecordedMovements>,
+    ) {
+        save_reader.clear();
+        // While surviving, the recording is of a run that hasn't won yet
+        if *game_mode.get() == GameMode::Survive || movements.inputs.is_empty() {
+            warn!("There's no winning run to save yet");
+            return;
+        }
+
+        let replay = ReplayFile::new(
+            &level_id,
+            1. / fixed_time.timestep().as_secs_f64(),
+            rng.seed(),
+            &positions,
+            &movements,
+        );
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis());
+        // Never overwrite an earlier replay, even one saved in the same millisecond
+        let mut path = storage::data_path(REPLAY_DIR).join(format!("replay-{timestamp}.ron"));
+        let mut copy = 1;
+        while path.exists() {
+            path = storage::data_path(REPLAY_DIR).join(format!("replay-{timestamp}-{copy}.ron"));
+            copy += 1;
+        }
+
+        match replay.save(&path) {
+            Ok(()) => info!("Saved replay to {}", path.display()),
+            Err(err) => error!("Failed to save replay: {err}"),
+        }
+    }
+
+    /// Loaded in defend mode, the replay is the run that the obstacles go against next.
+    fn load_replay(
+        mut load_reader: EventReader<LoadReplay>,
+        game_mode: Res<State<GameMode>>,
+        fixed_time: Res<Time<Fixed>>,
+        level_id: Res<LevelId>,
+        mut rng: ResMut<GameRng>,
+        mut positions: ResMut<RecordedPositions>,
+        mut movements: ResMut<RecordedMovements>,
+    ) {
+        for LoadReplay(path) in load_reader.read() {
+            if *game_mode.get() != GameMode::Defend {
+                warn!("Replays can only be loaded in defend mode, to stop them with obstacles");
+                continue;
+            }
+            let replay = match ReplayFile::load(path).and_then(|replay| {
+                replay.check_tick_rate(1. / fixed_time.timestep().as_secs_f64())?;
+                Ok(replay)
+            }) {
+                Ok(replay) => replay,
+                Err(err) => {
+                    error!("Failed to load replay from {}: {err}", path.display());
+                    continue;
+                }
+            };
+            if replay.header.level_id != level_id.0 {
+                warn!(
+                    "Replay was recorded in level `{}`, but `{}` is loaded",
+                    replay.header.level_id, level_id.0
+                );
+            }
+
+            positions.positions = replay.positions;
+            positions.last_played_frame = 0;
+            movements.clear();
+            movements.inputs = replay.inputs;
+            rng.reseed(replay.header.seed);
+            info!("Loaded replay from {}", path.display());
+        }
+    }
+}
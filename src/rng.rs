@@ -0,0 +1,94 @@
+//! Seeded randomness for gameplay, so a run can be played again exactly from its seed.
+//!
+//! Every random decision of the game draws from [`GameRng`], which starts over from its seed at
+//! the start of each run. The seed is shown in the HUD, can be typed in or rolled from the main
+//! menu, or picked when starting the game with `--seed <number>`.
+//! Sound variants are the exception: which one plays depends on how many sounds are still playing,
+//! so they'd make runs diverge.
+
+use bevy::prelude::*;
+use rand::{
+    Rng, SeedableRng,
+    distr::uniform::{SampleRange, SampleUniform},
+    rngs::StdRng,
+};
+
+use crate::{GameState, modes::RestartRun};
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameRng>()
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Menu,
+                    entered: GameState::Game,
+                },
+                Self::start_over,
+            )
+            .add_systems(
+                FixedPreUpdate,
+                Self::start_over
+                    .run_if(on_event::<RestartRun>)
+                    .before(crate::update_state),
+            );
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Default for GameRng {
+    /// A random seed, small enough to be read out in a bug report.
+    fn default() -> Self {
+        Self::new(rand::random_range(0..1_000_000))
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed given with `--seed` on the command line, if any.
+    pub fn from_args() -> Option<Self> {
+        let mut args = std::env::args().skip_while(|arg| arg != "--seed").skip(1);
+        let seed = args.next()?;
+        match seed.parse() {
+            Ok(seed) => Some(Self::new(seed)),
+            Err(err) => {
+                error!("Invalid seed `{seed}`: {err}");
+                None
+            }
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Use another seed, starting its sequence from the beginning.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    pub fn random_range<T: SampleUniform, R: SampleRange<T>>(&mut self, range: R) -> T {
+        self.rng.random_range(range)
+    }
+}
+
+impl RngPlugin {
+    /// Every run with the same seed draws the same numbers.
+    fn start_over(mut rng: ResMut<GameRng>) {
+        info!("Starting run with seed {}", rng.seed);
+        let seed = rng.seed;
+        rng.reseed(seed);
+    }
+}
@@ -0,0 +1,247 @@
+//! Keep score of how many times the player beat their own level, and the best runs so far.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState,
+    daily::DailyPlugin,
+    modes::{GameMode, GoalReached, RestartLoop, RestartRun},
+    obstacles::{GhostObstacle, ObstacleMarker},
+    player::PlayerDeath,
+    rng::GameRng,
+    storage,
+    versus::VersusMatch,
+};
+
+/// How many times the player can die in survive mode before the game is over.
+pub const STARTING_LIVES: u32 = 3;
+/// How many entries are kept in the high score table.
+const MAX_HIGH_SCORES: usize = 10;
+/// Where the high scores are saved, inside the data directory.
+const HIGH_SCORES_FILE: &str = "highscores.ron";
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .init_resource::<LoopStartScore>()
+            .insert_resource(HighScores::load())
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Menu,
+                    entered: GameState::Game,
+                },
+                Self::spawn_hud,
+            )
+            .add_systems(
+                FixedPreUpdate,
+                (
+                    Self::count_loop
+                        .run_if(on_event::<GoalReached>.and(in_state(GameMode::Survive))),
+                    // Deaths cost points instead in a versus match
+                    Self::lose_life.run_if(
+                        on_event::<PlayerDeath>
+                            .and(in_state(GameMode::Survive))
+                            .and(not(resource_exists::<VersusMatch>)),
+                    ),
+                )
+                    .before(crate::update_state)
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_systems(
+                FixedPreUpdate,
+                (
+                    // After the daily challenge got the score of the attempt
+                    Self::reset_score
+                        .run_if(on_event::<RestartRun>)
+                        .after(DailyPlugin::record_result),
+                    // The loop was already counted once its run reached the goal
+                    Self::uncount_loop
+                        .run_if(on_event::<RestartLoop>.and(not(in_state(GameMode::Survive)))),
+                )
+                    .before(crate::update_state),
+            )
+            .add_systems(
+                Update,
+                (Self::tick_time, Self::update_hud).run_if(in_state(GameState::Game)),
+            )
+            .add_systems(OnEnter(GameState::GameOver), Self::record_high_score)
+            .add_systems(
+                OnEnter(GameState::Menu),
+                crate::menu::despawn_screen::<ScoreHud>,
+            );
+    }
+}
+
+#[derive(Debug, Resource, Clone)]
+pub struct Score {
+    /// How many times the level was beaten
+    pub loops: u32,
+    /// Sum of the obstacles that were in the level each time it was beaten
+    pub obstacles_survived: u32,
+    /// Time spent playing
+    pub time: Duration,
+    pub lives: u32,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            loops: 0,
+            obstacles_survived: 0,
+            time: Duration::ZERO,
+            lives: STARTING_LIVES,
+        }
+    }
+}
+
+impl Score {
+    pub fn points(&self) -> u32 {
+        self.loops * 100 + self.obstacles_survived * 25
+    }
+}
+
+/// What the score was before the current loop was counted, to take it back if the loop is
+/// restarted.
+#[derive(Debug, Resource, Default)]
+struct LoopStartScore {
+    loops: u32,
+    obstacles_survived: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub points: u32,
+    pub loops: u32,
+    pub obstacles_survived: u32,
+    pub time_secs: f32,
+}
+
+/// Best scores, saved between sessions.
+#[derive(Debug, Resource, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    fn load() -> Self {
+        storage::load_ron(storage::data_path(HIGH_SCORES_FILE)).unwrap_or_else(|err| {
+            info!("No high scores loaded: {err}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) {
+        if let Err(err) = storage::save_ron(storage::data_path(HIGH_SCORES_FILE), self) {
+            error!("Failed to save high scores: {err}");
+        }
+    }
+
+    /// Add a score to the table, returning its position if it made the cut.
+    pub fn insert(&mut self, score: &Score) -> Option<usize> {
+        let entry = HighScoreEntry {
+            points: score.points(),
+            loops: score.loops,
+            obstacles_survived: score.obstacles_survived,
+            time_secs: score.time.as_secs_f32(),
+        };
+        // Higher points first, and faster runs break ties
+        let position = self.entries.partition_point(|other| {
+            other.points > entry.points
+                || (other.points == entry.points && other.time_secs <= entry.time_secs)
+        });
+        if position >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.entries.insert(position, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(position)
+    }
+}
+
+/// Marker for the score display while playing
+#[derive(Debug, Component)]
+struct ScoreHud;
+
+impl ScorePlugin {
+    fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+        commands.spawn((
+            ScoreHud,
+            Text::default(),
+            TextFont {
+                font_size: 24.,
+                font: asset_server.load("fonts/capitolcity.ttf"),
+                ..Default::default()
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                right: Val::Px(20.),
+                ..Default::default()
+            },
+        ));
+    }
+
+    fn update_hud(
+        score: Res<Score>,
+        rng: Res<GameRng>,
+        mut hud: Single<&mut Text, With<ScoreHud>>,
+    ) {
+        let seconds = score.time.as_secs();
+        hud.0 = format!(
+            "Score: {}   Loops: {}   Lives: {}   Time: {}:{:02}   Seed: {}",
+            score.points(),
+            score.loops,
+            score.lives,
+            seconds / 60,
+            seconds % 60,
+            rng.seed()
+        );
+    }
+
+    fn tick_time(mut score: ResMut<Score>, time: Res<Time>) {
+        score.time += time.delta();
+    }
+
+    fn count_loop(
+        mut score: ResMut<Score>,
+        mut loop_start: ResMut<LoopStartScore>,
+        obstacles: Query<(), (With<ObstacleMarker>, Without<GhostObstacle>)>,
+    ) {
+        *loop_start = LoopStartScore {
+            loops: score.loops,
+            obstacles_survived: score.obstacles_survived,
+        };
+        score.loops += 1;
+        score.obstacles_survived += obstacles.iter().count() as u32;
+        info!("Loop {} done, score is {}", score.loops, score.points());
+    }
+
+    fn lose_life(mut score: ResMut<Score>, mut state: ResMut<NextState<GameState>>) {
+        score.lives = score.lives.saturating_sub(1);
+        info!("Lost a life, {} left", score.lives);
+        if score.lives == 0 {
+            state.set(GameState::GameOver);
+        }
+    }
+
+    fn reset_score(mut score: ResMut<Score>) {
+        *score = Score::default();
+    }
+
+    fn uncount_loop(mut score: ResMut<Score>, loop_start: Res<LoopStartScore>) {
+        score.loops = loop_start.loops;
+        score.obstacles_survived = loop_start.obstacles_survived;
+    }
+
+    fn record_high_score(score: Res<Score>, mut high_scores: ResMut<HighScores>) {
+        if let Some(position) = high_scores.insert(&score) {
+            info!("New high score! #{}", position + 1);
+            high_scores.save();
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Player settings, saved to disk and applied when the game starts and whenever they change.
+
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use bevy_framepace::{FramepaceSettings, Limiter};
+use serde::{Deserialize, Serialize};
+
+use crate::{audio::SfxCategory, storage};
+
+/// Where the settings are saved, inside the data directory.
+const SETTINGS_FILE: &str = "settings.ron";
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>().add_systems(
+            Update,
+            (Self::apply_window_settings, Self::apply_frame_cap)
+                .run_if(resource_changed::<Settings>),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FrameCap {
+    /// Match the refresh rate of the display
+    #[default]
+    Display,
+    Fps30,
+    Fps60,
+    Fps120,
+    Fps144,
+    Unlimited,
+}
+
+impl FrameCap {
+    const ALL: [FrameCap; 6] = [
+        FrameCap::Display,
+        FrameCap::Fps30,
+        FrameCap::Fps60,
+        FrameCap::Fps120,
+        FrameCap::Fps144,
+        FrameCap::Unlimited,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|cap| *cap == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FrameCap::Display => "Display",
+            FrameCap::Fps30 => "30 FPS",
+            FrameCap::Fps60 => "60 FPS",
+            FrameCap::Fps120 => "120 FPS",
+            FrameCap::Fps144 => "144 FPS",
+            FrameCap::Unlimited => "Unlimited",
+        }
+    }
+
+    fn limiter(&self) -> Limiter {
+        match self {
+            FrameCap::Display => Limiter::Auto,
+            FrameCap::Fps30 => Limiter::from_framerate(30.),
+            FrameCap::Fps60 => Limiter::from_framerate(60.),
+            FrameCap::Fps120 => Limiter::from_framerate(120.),
+            FrameCap::Fps144 => Limiter::from_framerate(144.),
+            FrameCap::Unlimited => Limiter::Off,
+        }
+    }
+}
+
+/// What's played: the level file, or a course generated from the seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Course {
+    #[default]
+    Level,
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Course {
+    const ALL: [Course; 4] = [Course::Level, Course::Easy, Course::Normal, Course::Hard];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|course| *course == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Course::Level => "Level file",
+            Course::Easy => "Generated, easy",
+            Course::Normal => "Generated, normal",
+            Course::Hard => "Generated, hard",
+        }
+    }
+
+    /// Difficulty the course is generated with, if it's generated.
+    pub fn difficulty(&self) -> Option<f32> {
+        match self {
+            Course::Level => None,
+            Course::Easy => Some(0.2),
+            Course::Normal => Some(0.5),
+            Course::Hard => Some(0.9),
+        }
+    }
+}
+
+#[derive(Debug, Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Between 0 and 1
+    pub music_volume: f32,
+    /// Between 0 and 1
+    pub sfx_volume: f32,
+    /// Volume of each category of sound effects, on top of `sfx_volume`. Between 0 and 1, missing
+    /// categories are at full volume.
+    pub bus_volumes: HashMap<SfxCategory, f32>,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub frame_cap: FrameCap,
+    /// Replay every previous winning run along with the latest one, which all have to be stopped
+    pub replay_all_runs: bool,
+    pub course: Course,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_volume: 0.5,
+            sfx_volume: 1.,
+            bus_volumes: HashMap::new(),
+            fullscreen: true,
+            vsync: false,
+            frame_cap: FrameCap::Display,
+            replay_all_runs: false,
+            course: Course::Level,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::load_ron(storage::data_path(SETTINGS_FILE)).unwrap_or_else(|err| {
+            info!("Using the default settings: {err}");
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = storage::save_ron(storage::data_path(SETTINGS_FILE), self) {
+            error!("Failed to save the settings: {err}");
+        }
+    }
+
+    pub fn bus_volume(&self, category: SfxCategory) -> f32 {
+        self.bus_volumes.get(&category).copied().unwrap_or(1.)
+    }
+
+    pub fn set_bus_volume(&mut self, category: SfxCategory, volume: f32) {
+        self.bus_volumes.insert(category, volume);
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+}
+
+impl SettingsPlugin {
+    fn apply_window_settings(
+        settings: Res<Settings>,
+        mut window: Single<&mut Window, With<PrimaryWindow>>,
+    ) {
+        let (mode, present_mode) = (settings.window_mode(), settings.present_mode());
+        // Only touch the window when needed, changing its mode makes it flicker
+        if window.mode != mode {
+            window.mode = mode;
+        }
+        if window.present_mode != present_mode {
+            window.present_mode = present_mode;
+        }
+    }
+
+    fn apply_frame_cap(settings: Res<Settings>, mut framepace: ResMut<FramepaceSettings>) {
+        framepace.limiter = settings.frame_cap.limiter();
+    }
+}
@@ -0,0 +1,60 @@
+//! Reading and writing the game's files on disk.
+//! Everything is stored as RON under [`DATA_DIR`], relative to where the game is run from.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Directory where everything the game saves ends up.
+pub const DATA_DIR: &str = "saves";
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::error::SpannedError),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Io(err) => write!(f, "could not access file: {err}"),
+            StorageError::Serialize(err) => write!(f, "could not serialize data: {err}"),
+            StorageError::Deserialize(err) => write!(f, "could not parse file: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<io::Error> for StorageError {
+    fn from(err: io::Error) -> Self {
+        StorageError::Io(err)
+    }
+}
+
+/// Path of a file inside the [`DATA_DIR`].
+pub fn data_path(relative: impl AsRef<Path>) -> PathBuf {
+    Path::new(DATA_DIR).join(relative)
+}
+
+/// Serialize `value` as RON into `path`, creating the parent directories if needed.
+pub fn save_ron<T: Serialize>(path: impl AsRef<Path>, value: &T) -> Result<(), StorageError> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(StorageError::Serialize)?;
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Read a RON file from `path`.
+pub fn load_ron<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, StorageError> {
+    let contents = fs::read_to_string(path)?;
+    ron::from_str(&contents).map_err(StorageError::Deserialize)
+}
@@ -0,0 +1,333 @@
+//! Timeline of the replay, shown while placing obstacles.
+//!
+//! It plays the recorded run back at the bottom of the screen: drag along the track to go to any
+//! frame, or let it play at different speeds. The replay's position at that frame is drawn on top
+//! of its path, with the lasers that would strike at that moment, so obstacles can be timed.
+
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+
+use crate::{
+    GameState,
+    menu::{HOVERED_BUTTON, NORMAL_BUTTON, TEXT_COLOR},
+    modes::GameMode,
+    obstacles::{Flicker, GhostObstacle},
+    player::record_position::RecordedPositions,
+};
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Timeline>()
+            .add_systems(OnEnter(GameMode::Defend), Self::spawn_timeline)
+            .add_systems(OnExit(GameMode::Defend), Self::despawn_timeline)
+            .add_systems(
+                Update,
+                (
+                    Self::timeline_action,
+                    Self::scrub,
+                    Self::play,
+                    Self::update_timeline,
+                    Self::draw_frame,
+                )
+                    .chain()
+                    .run_if(in_state(GameMode::Defend).and(in_state(GameState::Game))),
+            );
+    }
+}
+
+/// Speeds the timeline can play at, relative to the game.
+const SPEEDS: [f32; 5] = [0.25, 0.5, 1., 2., 4.];
+
+/// What the timeline shows, and how it plays.
+#[derive(Debug, Resource)]
+struct Timeline {
+    /// Frame of the recorded run being shown, fractional so slow speeds still move
+    frame: f32,
+    /// Index in [`SPEEDS`]
+    speed: usize,
+    playing: bool,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            frame: 0.,
+            speed: 2,
+            playing: true,
+        }
+    }
+}
+
+impl Timeline {
+    fn speed(&self) -> f32 {
+        SPEEDS[self.speed]
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct TimelineMarker;
+
+#[derive(Component, Debug)]
+enum TimelineButton {
+    PlayPause,
+    Slower,
+    Faster,
+}
+
+/// The bar that can be dragged along to pick a frame
+#[derive(Component)]
+struct TimelineTrack;
+
+/// Where the shown frame is on the track
+#[derive(Component)]
+struct TimelineCursor;
+
+#[derive(Component, Debug)]
+enum TimelineLabel {
+    PlayPause,
+    Speed,
+    Frame,
+}
+
+impl TimelinePlugin {
+    fn spawn_timeline(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        mut timeline: ResMut<Timeline>,
+    ) {
+        *timeline = Timeline::default();
+
+        let text_font = TextFont {
+            font_size: 20.,
+            font: asset_server.load("fonts/capitolcity.ttf"),
+            ..Default::default()
+        };
+        let button_node = Node {
+            width: Val::Px(90.),
+            height: Val::Px(40.),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        };
+
+        commands.spawn((
+            TimelineMarker,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.),
+                left: Val::Percent(10.),
+                width: Val::Percent(80.),
+                height: Val::Px(60.),
+                padding: UiRect::horizontal(Val::Px(10.)),
+                column_gap: Val::Px(10.),
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            // Hovering the timeline keeps clicks from placing the obstacle
+            Interaction::default(),
+            BackgroundColor(Color::srgba(0., 0., 0., 0.6)),
+            children![
+                timeline_button(
+                    TimelineButton::PlayPause,
+                    button_node.clone(),
+                    (
+                        Text::default(),
+                        text_font.clone(),
+                        TextColor(TEXT_COLOR),
+                        TimelineLabel::PlayPause,
+                    ),
+                ),
+                timeline_button(
+                    TimelineButton::Slower,
+                    button_node.clone(),
+                    (Text::new("-"), text_font.clone(), TextColor(TEXT_COLOR)),
+                ),
+                (
+                    Text::default(),
+                    text_font.clone(),
+                    TextColor(TEXT_COLOR),
+                    TimelineLabel::Speed,
+                ),
+                timeline_button(
+                    TimelineButton::Faster,
+                    button_node,
+                    (Text::new("+"), text_font.clone(), TextColor(TEXT_COLOR)),
+                ),
+                (
+                    TimelineTrack,
+                    Button,
+                    RelativeCursorPosition::default(),
+                    Node {
+                        flex_grow: 1.,
+                        height: Val::Px(16.),
+                        ..Default::default()
+                    },
+                    BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
+                    children![(
+                        TimelineCursor,
+                        Node {
+                            position_type: PositionType::Absolute,
+                            width: Val::Px(4.),
+                            height: Val::Px(30.),
+                            top: Val::Px(-7.),
+                            ..Default::default()
+                        },
+                        BackgroundColor(Color::WHITE),
+                    )],
+                ),
+                (
+                    Text::default(),
+                    text_font,
+                    TextColor(TEXT_COLOR),
+                    TimelineLabel::Frame,
+                ),
+            ],
+        ));
+    }
+
+    fn despawn_timeline(mut commands: Commands, query: Query<Entity, With<TimelineMarker>>) {
+        for entity in query {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    fn timeline_action(
+        action: Query<
+            (&Interaction, &TimelineButton, &mut BackgroundColor),
+            (Changed<Interaction>, With<Button>),
+        >,
+        mut timeline: ResMut<Timeline>,
+    ) {
+        for (interaction, timeline_action, mut background_color) in action {
+            if *interaction == Interaction::Pressed {
+                match timeline_action {
+                    TimelineButton::PlayPause => timeline.playing = !timeline.playing,
+                    TimelineButton::Slower => timeline.speed = timeline.speed.saturating_sub(1),
+                    TimelineButton::Faster => {
+                        timeline.speed = (timeline.speed + 1).min(SPEEDS.len() - 1);
+                    }
+                }
+            }
+
+            *background_color = match interaction {
+                Interaction::None => NORMAL_BUTTON.into(),
+                Interaction::Pressed | Interaction::Hovered => HOVERED_BUTTON.into(),
+            }
+        }
+    }
+
+    /// Go to the frame under the mouse while the track is held.
+    fn scrub(
+        track: Single<(&Interaction, &RelativeCursorPosition), With<TimelineTrack>>,
+        recorded_positions: Res<RecordedPositions>,
+        mut timeline: ResMut<Timeline>,
+    ) {
+        let (interaction, cursor) = track.into_inner();
+        if *interaction != Interaction::Pressed {
+            return;
+        }
+        // The cursor position is relative to the center of the track
+        let Some(cursor) = cursor.normalized else {
+            return;
+        };
+        let progress = (cursor.x + 0.5).clamp(0., 1.);
+        timeline.frame = progress * last_frame(&recorded_positions) as f32;
+        timeline.playing = false;
+    }
+
+    fn play(
+        time: Res<Time<Real>>,
+        fixed_time: Res<Time<Fixed>>,
+        recorded_positions: Res<RecordedPositions>,
+        mut timeline: ResMut<Timeline>,
+    ) {
+        if !timeline.playing {
+            return;
+        }
+        let frames_per_second = 1. / fixed_time.timestep().as_secs_f32();
+        let end = last_frame(&recorded_positions) as f32;
+        timeline.frame += time.delta_secs() * frames_per_second * timeline.speed();
+        // Start over once the end is reached
+        if timeline.frame > end {
+            timeline.frame = 0.;
+        }
+    }
+
+    fn update_timeline(
+        timeline: Res<Timeline>,
+        recorded_positions: Res<RecordedPositions>,
+        mut cursor: Single<&mut Node, With<TimelineCursor>>,
+        labels: Query<(&mut Text, &TimelineLabel)>,
+    ) {
+        let end = last_frame(&recorded_positions);
+        let progress = if end == 0 {
+            0.
+        } else {
+            timeline.frame / end as f32
+        };
+        cursor.left = Val::Percent(progress * 100.);
+
+        for (mut text, label) in labels {
+            text.0 = match label {
+                TimelineLabel::PlayPause if timeline.playing => "Pause".to_string(),
+                TimelineLabel::PlayPause => "Play".to_string(),
+                TimelineLabel::Speed => format!("{}x", timeline.speed()),
+                TimelineLabel::Frame => format!("{} / {end}", timeline.frame as u32),
+            };
+        }
+    }
+
+    /// Draw where the replay is at the shown frame, and which lasers strike then. The laser being
+    /// placed is drawn in white, to time it against the others.
+    fn draw_frame(
+        timeline: Res<Timeline>,
+        recorded_positions: Res<RecordedPositions>,
+        lasers: Query<(&Transform, &Flicker, Has<GhostObstacle>)>,
+        mut gizmos: Gizmos,
+    ) {
+        let frame = timeline.frame as u32;
+        let positions = &recorded_positions.positions;
+        let index = positions.partition_point(|(position_frame, _, _)| *position_frame <= frame);
+        if let Some((_, position, _)) = positions.get(index.saturating_sub(1)) {
+            gizmos.rect_2d(
+                Isometry2d::from_translation(position.truncate()),
+                Vec2::splat(40.),
+                Color::srgb(0.3, 0.8, 1.),
+            );
+        }
+
+        for (transform, flicker, is_ghost) in lasers {
+            let x = transform.translation.x;
+            let color = if is_ghost {
+                Color::WHITE
+            } else {
+                Color::srgb(1.0, 0.2, 0.3)
+            };
+            let color = if flicker.is_active_at(frame) {
+                color
+            } else {
+                color.with_alpha(0.15)
+            };
+            gizmos.line_2d(vec2(x, -1000.), vec2(x, 1000.), color);
+        }
+    }
+}
+
+fn timeline_button(action: TimelineButton, node: Node, text: impl Bundle) -> impl Bundle {
+    (
+        Button,
+        node,
+        BackgroundColor(NORMAL_BUTTON),
+        action,
+        children![text],
+    )
+}
+
+/// Last frame of the recorded run.
+fn last_frame(recorded_positions: &RecordedPositions) -> u32 {
+    recorded_positions
+        .positions
+        .last()
+        .map_or(0, |(frame, _, _)| *frame)
+}
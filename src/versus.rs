@@ -0,0 +1,310 @@
+//! Two players taking turns on the same computer: one runs the course, then the other places
+//! obstacles to stop the replay of that run. Once the replay is stopped, they swap roles.
+//!
+//! The runner scores less for every death on the way to the goal, and the defender less for every
+//! time the replay got past their obstacle. After both played each role for the number of rounds
+//! of the match, the one with the most points wins.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState,
+    modes::{GameMode, RestartRun, Restarting},
+    player::PlayerDeath,
+    storage,
+};
+
+/// Where the profiles are saved, inside the data directory.
+const PROFILES_FILE: &str = "profiles.ron";
+/// Points for reaching the goal without dying, one less for each death
+pub const RUN_POINTS: u32 = 3;
+/// Points for stopping the replay on the first try, one less each time it gets past
+pub const DEFEND_POINTS: u32 = 3;
+
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Profiles::load())
+            .add_event::<StartVersus>()
+            .add_systems(Update, Self::start_versus.run_if(on_event::<StartVersus>))
+            .add_systems(
+                FixedPreUpdate,
+                Self::count_death
+                    .run_if(
+                        on_event::<PlayerDeath>
+                            .and(in_state(GameMode::Survive))
+                            .and(resource_exists::<VersusMatch>),
+                    )
+                    .before(crate::update_state),
+            )
+            .add_systems(
+                OnTransition {
+                    exited: GameMode::Replay,
+                    entered: GameMode::Defend,
+                },
+                Self::count_replay_past.run_if(resource_exists::<VersusMatch>),
+            )
+            .add_systems(
+                OnTransition {
+                    exited: GameMode::Replay,
+                    entered: GameMode::Survive,
+                },
+                // Restarting isn't the end of a turn
+                Self::end_turn
+                    .run_if(resource_exists::<VersusMatch>.and(not(resource_exists::<Restarting>))),
+            )
+            .add_systems(OnEnter(GameState::MatchOver), Self::record_match)
+            .add_systems(OnEnter(GameState::Menu), Self::leave_match)
+            .add_systems(
+                Update,
+                Self::update_banner.run_if(in_state(GameState::Game)),
+            );
+    }
+}
+
+/// Start a match between two profiles, `players[0]` running first.
+#[derive(Debug, Event)]
+pub struct StartVersus {
+    pub players: [String; 2],
+    pub rounds: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub matches_played: u32,
+    pub matches_won: u32,
+}
+
+/// Everyone who played a match on this computer.
+#[derive(Debug, Resource, Serialize, Deserialize)]
+pub struct Profiles(pub Vec<Profile>);
+
+impl Default for Profiles {
+    fn default() -> Self {
+        Self(vec![Profile::new("Player 1"), Profile::new("Player 2")])
+    }
+}
+
+impl Profile {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            matches_played: 0,
+            matches_won: 0,
+        }
+    }
+}
+
+impl Profiles {
+    fn load() -> Self {
+        let mut profiles: Self = storage::load_ron(storage::data_path(PROFILES_FILE))
+            .unwrap_or_else(|err| {
+                info!("No profiles loaded: {err}");
+                Self::default()
+            });
+        // A match needs two of them
+        while profiles.0.len() < 2 {
+            let name = format!("Player {}", profiles.0.len() + 1);
+            profiles.add(name);
+        }
+        profiles
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = storage::save_ron(storage::data_path(PROFILES_FILE), self) {
+            error!("Failed to save profiles: {err}");
+        }
+    }
+
+    /// Add a profile, unless there's already one with that name.
+    pub fn add(&mut self, name: String) {
+        if !self.0.iter().any(|profile| profile.name == name) {
+            self.0.push(Profile::new(name));
+        }
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut Profile> {
+        self.0.iter_mut().find(|profile| profile.name == name)
+    }
+}
+
+/// The match being played, if there's one.
+#[derive(Debug, Resource, Clone)]
+pub struct VersusMatch {
+    pub players: [String; 2],
+    pub scores: [u32; 2],
+    /// Index of the player running the course, the other one defends
+    pub runner: usize,
+    /// Rounds played so far, where each player ran once
+    pub round: u32,
+    pub rounds: u32,
+    /// Deaths of the runner during this turn
+    pub deaths: u32,
+    /// Times the replay got past the defender's obstacle during this turn
+    pub replays_past: u32,
+}
+
+impl VersusMatch {
+    pub fn new(players: [String; 2], rounds: u32) -> Self {
+        Self {
+            players,
+            scores: [0; 2],
+            runner: 0,
+            round: 0,
+            rounds,
+            deaths: 0,
+            replays_past: 0,
+        }
+    }
+
+    pub fn defender(&self) -> usize {
+        1 - self.runner
+    }
+
+    /// Score the turn that just ended, and swap roles. Returns whether the match is over.
+    pub fn end_turn(&mut self) -> bool {
+        self.scores[self.runner] += RUN_POINTS.saturating_sub(self.deaths);
+        let defender = self.defender();
+        self.scores[defender] += DEFEND_POINTS.saturating_sub(self.replays_past);
+        self.deaths = 0;
+        self.replays_past = 0;
+
+        self.runner = defender;
+        if self.runner == 0 {
+            self.round += 1;
+        }
+        self.is_over()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.round >= self.rounds
+    }
+
+    /// Index of the player with the most points, if they're not tied.
+    pub fn winner(&self) -> Option<usize> {
+        match self.scores[0].cmp(&self.scores[1]) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+/// Shows whose turn it is, and the score of the match.
+#[derive(Component)]
+struct VersusBanner;
+
+impl VersusPlugin {
+    fn start_versus(
+        mut commands: Commands,
+        mut start_reader: EventReader<StartVersus>,
+        state: Res<State<GameState>>,
+        mut next_state: ResMut<NextState<GameState>>,
+        mut restart_writer: EventWriter<RestartRun>,
+    ) {
+        let Some(start) = start_reader.read().last() else {
+            return;
+        };
+        info!(
+            "Starting a match of {} rounds between {} and {}",
+            start.rounds, start.players[0], start.players[1]
+        );
+        commands.insert_resource(VersusMatch::new(start.players.clone(), start.rounds));
+        // A rematch starts over from a clean level, the first match is already clean
+        if *state.get() != GameState::Menu {
+            restart_writer.write(RestartRun);
+        }
+        next_state.set(GameState::Game);
+    }
+
+    fn count_death(mut versus: ResMut<VersusMatch>) {
+        versus.deaths += 1;
+    }
+
+    fn count_replay_past(mut versus: ResMut<VersusMatch>) {
+        versus.replays_past += 1;
+    }
+
+    fn end_turn(mut versus: ResMut<VersusMatch>, mut state: ResMut<NextState<GameState>>) {
+        let over = versus.end_turn();
+        info!(
+            "Turn over, {} {} - {} {}",
+            versus.players[0], versus.scores[0], versus.scores[1], versus.players[1]
+        );
+        if over {
+            state.set(GameState::MatchOver);
+        }
+    }
+
+    /// Going back to the menu gives up on the match.
+    fn leave_match(mut commands: Commands, banner: Query<Entity, With<VersusBanner>>) {
+        commands.remove_resource::<VersusMatch>();
+        for entity in &banner {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    fn record_match(versus: Res<VersusMatch>, mut profiles: ResMut<Profiles>) {
+        for (index, name) in versus.players.iter().enumerate() {
+            let Some(profile) = profiles.get_mut(name) else {
+                continue;
+            };
+            profile.matches_played += 1;
+            if versus.winner() == Some(index) {
+                profile.matches_won += 1;
+            }
+        }
+        profiles.save();
+    }
+
+    fn update_banner(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        versus: Option<Res<VersusMatch>>,
+        banner: Option<Single<(Entity, &mut Text), With<VersusBanner>>>,
+    ) {
+        let Some(versus) = versus else {
+            if let Some(banner) = banner {
+                commands.entity(banner.0).despawn();
+            }
+            return;
+        };
+        let text = format!(
+            "Round {}/{}: {} runs, {} defends   {} {} - {} {}",
+            (versus.round + 1).min(versus.rounds),
+            versus.rounds,
+            versus.players[versus.runner],
+            versus.players[versus.defender()],
+            versus.players[0],
+            versus.scores[0],
+            versus.scores[1],
+            versus.players[1]
+        );
+        match banner {
+            Some(banner) => {
+                let (_, mut banner_text) = banner.into_inner();
+                banner_text.0 = text;
+            }
+            None => {
+                commands.spawn((
+                    VersusBanner,
+                    Text::new(text),
+                    TextFont {
+                        font_size: 24.,
+                        font: asset_server.load("fonts/capitolcity.ttf"),
+                        ..Default::default()
+                    },
+                    Node {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(40.),
+                        right: Val::Px(20.),
+                        ..Default::default()
+                    },
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,667 @@
+//! Simulate whole runs without a window, and check how they end.
+
+use bevy::prelude::*;
+use gmtk::{
+    GameState, LevelId, SimulationTick, TILE_SIZE, VIEW_SIZE,
+    campaign::{CAMPAIGN, CampaignProgress},
+    daily::{Date, daily_level},
+    environment::LevelGeometry,
+    generator::{COURSE_LENGTH, JumpPhysics, LevelGenerator},
+    headless::{HeadlessAppBuilder, Simulation},
+    input::{Action, InputMap},
+    level::{CurrentLevel, LevelAsset, PlacedObstacle, PlacedPatrol, TileRect},
+    modes::{GameMode, RestartLoop, RestartRun},
+    obstacles::{
+        FakeLaser, Flicker, GhostObstacle, ObstacleMarker, ObstacleType,
+        shop::{BuildPoints, POINTS_PER_LOOP},
+    },
+    player::{
+        record_movement::RecordedMovements,
+        record_position::RecordedPositions,
+        replay_file::{LoadReplay, REPLAY_FORMAT_VERSION, ReplayError, ReplayFile},
+    },
+    rng::GameRng,
+    versus::{DEFEND_POINTS, RUN_POINTS, VersusMatch},
+};
+
+/// Long enough to cross the default level a few times over.
+const MAX_TICKS: u32 = 1_000;
+
+fn flat_level(obstacles: Vec<PlacedObstacle>) -> LevelAsset {
+    LevelAsset {
+        name: "Flat".to_string(),
+        length: 40,
+        start: (1, 3),
+        goal: (30, 3),
+        ground: vec![TileRect {
+            x: 0,
+            y: 0,
+            width: 40,
+            height: 3,
+        }],
+        platforms: Vec::new(),
+        obstacles,
+    }
+}
+
+#[test]
+fn player_spawns_on_the_ground() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+    let start = app.player_position().expect("the player should be spawned");
+
+    app.tick(120);
+
+    let landed = app.player_position().unwrap();
+    assert!((landed.x - start.x).abs() < 1., "moved without any input");
+    assert!(landed.y <= start.y, "went up without jumping");
+    assert_eq!(app.outcomes().deaths, 0);
+}
+
+#[test]
+fn idle_player_never_reaches_the_goal() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+
+    let reached = app.tick_until(MAX_TICKS, |outcomes| outcomes.goals_reached > 0);
+
+    assert!(!reached);
+    assert_eq!(app.outcomes().deaths, 0);
+}
+
+#[test]
+fn running_right_reaches_the_goal() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+    app.hold(KeyCode::ArrowRight);
+
+    let reached = app.tick_until(MAX_TICKS, |outcomes| outcomes.goals_reached > 0);
+
+    assert!(reached, "goal not reached in {MAX_TICKS} ticks");
+    app.tick(1);
+    assert_eq!(
+        *app.world().resource::<State<GameMode>>().get(),
+        GameMode::Defend
+    );
+}
+
+#[test]
+fn running_into_a_spike_kills_the_player() {
+    let mut app = HeadlessAppBuilder::default()
+        .with_level(flat_level(vec![PlacedObstacle {
+            kind: ObstacleType::Spike,
+            position: (10, 3),
+            patrol: None,
+        }]))
+        .build();
+    app.start_run();
+    app.hold(KeyCode::ArrowRight);
+
+    let died = app.tick_until(MAX_TICKS, |outcomes| outcomes.deaths > 0);
+
+    assert!(died, "survived the spike for {MAX_TICKS} ticks");
+    assert_eq!(app.outcomes().goals_reached, 0);
+}
+
+#[test]
+fn jumping_over_a_spike_reaches_the_goal() {
+    let level = flat_level(vec![PlacedObstacle {
+        kind: ObstacleType::Spike,
+        position: (10, 3),
+        patrol: None,
+    }]);
+    let jump_x = -VIEW_SIZE.x / 2. + 7. * TILE_SIZE;
+    let mut app = HeadlessAppBuilder::default().with_level(level).build();
+    app.start_run();
+    app.hold(KeyCode::ArrowRight);
+
+    // Jump a few tiles before the spike
+    for _ in 0..MAX_TICKS {
+        app.tick(1);
+        if app.player_position().unwrap().x >= jump_x {
+            break;
+        }
+    }
+    app.hold(KeyCode::Space);
+    app.tick(10);
+    app.release(KeyCode::Space);
+    let reached = app.tick_until(MAX_TICKS, |outcomes| outcomes.goals_reached > 0);
+
+    assert!(reached, "goal not reached in {MAX_TICKS} ticks");
+    assert_eq!(app.outcomes().deaths, 0);
+}
+
+#[test]
+fn falling_in_a_gap_kills_the_player() {
+    let mut level = flat_level(Vec::new());
+    level.ground = vec![
+        TileRect {
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 3,
+        },
+        TileRect {
+            x: 14,
+            y: 0,
+            width: 26,
+            height: 3,
+        },
+    ];
+    let mut app = HeadlessAppBuilder::default().with_level(level).build();
+    app.start_run();
+    app.hold(KeyCode::ArrowRight);
+
+    let died = app.tick_until(MAX_TICKS, |outcomes| outcomes.deaths > 0);
+
+    assert!(died, "survived the fall for {MAX_TICKS} ticks");
+    assert_eq!(app.outcomes().goals_reached, 0);
+}
+
+#[test]
+fn simulation_tick_stands_still_while_paused() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+    let tick = |app: &App| app.world().resource::<SimulationTick>().0;
+
+    let before = tick(&app);
+    app.tick(60);
+    assert!(tick(&app) > before, "the tick didn't advance while playing");
+
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Paused);
+    app.tick(1);
+    let paused = tick(&app);
+    app.tick(60);
+    assert_eq!(tick(&app), paused);
+}
+
+#[test]
+fn a_level_modified_while_paused_is_respawned_on_resume() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+    let mut geometry = app.world_mut().query_filtered::<(), With<LevelGeometry>>();
+    let before = geometry.iter(app.world()).count();
+
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Paused);
+    app.tick(1);
+    let level = app.world().resource::<CurrentLevel>().0.id();
+    app.world_mut()
+        .resource_mut::<Assets<LevelAsset>>()
+        .get_mut(level)
+        .unwrap()
+        .platforms
+        .push(TileRect {
+            x: 5,
+            y: 4,
+            width: 2,
+            height: 1,
+        });
+    app.tick(5);
+
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Game);
+    app.tick(1);
+    assert_eq!(geometry.iter(app.world()).count(), before + 1);
+}
+
+#[test]
+fn beating_the_level_pays_for_an_obstacle_from_the_shop() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+    app.hold(KeyCode::ArrowRight);
+    let reached = app.tick_until(MAX_TICKS, |outcomes| outcomes.goals_reached > 0);
+    assert!(reached, "goal not reached in {MAX_TICKS} ticks");
+    app.release(KeyCode::ArrowRight);
+    app.tick(1);
+
+    let points = |app: &App| app.world().resource::<BuildPoints>().0;
+    assert_eq!(points(&app), POINTS_PER_LOOP);
+    let mut ghosts = app
+        .world_mut()
+        .query_filtered::<&ObstacleType, With<GhostObstacle>>();
+    assert_eq!(ghosts.iter(app.world()).count(), 0);
+
+    // The spike is the first item of the shop
+    app.tap(KeyCode::Digit1);
+
+    assert_eq!(points(&app), POINTS_PER_LOOP - 1);
+    let bought: Vec<_> = ghosts.iter(app.world()).copied().collect();
+    assert_eq!(bought, [ObstacleType::Spike]);
+}
+
+#[test]
+fn restarting_the_run_draws_the_same_random_numbers() {
+    let mut app = HeadlessAppBuilder::default().with_seed(42).build();
+    app.start_run();
+    let draw = |app: &mut App| -> Vec<u32> {
+        let mut rng = app.world_mut().resource_mut::<GameRng>();
+        (0..5).map(|_| rng.random_range(0..1000)).collect()
+    };
+
+    let first = draw(&mut app);
+    app.world_mut().send_event(RestartRun);
+    app.tick(1);
+
+    assert_eq!(app.world().resource::<GameRng>().seed(), 42);
+    assert_eq!(draw(&mut app), first);
+}
+
+#[test]
+fn saws_placed_in_the_level_patrol_their_path() {
+    let level = flat_level(vec![PlacedObstacle {
+        kind: ObstacleType::Saw,
+        position: (20, 3),
+        patrol: Some(PlacedPatrol {
+            end: (25, 3),
+            speed: 3.,
+        }),
+    }]);
+    let mut app = HeadlessAppBuilder::default().with_level(level).build();
+    app.start_run();
+    let mut saw = app
+        .world_mut()
+        .query_filtered::<&Transform, With<ObstacleType>>();
+    let start = saw.single(app.world()).unwrap().translation.x;
+
+    app.tick(20);
+
+    let position = saw.single(app.world()).unwrap().translation.x;
+    assert!(position - start > 30., "the saw didn't move along its path");
+}
+
+#[test]
+fn daily_challenge_dates_count_from_the_epoch() {
+    let date = |days| Date::from_days_since_epoch(days).to_string();
+    assert_eq!(date(0), "1970-01-01");
+    assert_eq!(date(19_723), "2024-01-01");
+    assert_eq!(date(19_782), "2024-02-29");
+}
+
+#[test]
+fn daily_level_is_the_same_for_everyone_on_a_given_day() {
+    let today = Date::from_days_since_epoch(20_000);
+    let tomorrow = Date::from_days_since_epoch(20_001);
+
+    assert_eq!(daily_level(today), daily_level(today));
+    assert_ne!(daily_level(today), daily_level(tomorrow));
+}
+
+#[test]
+fn versus_turns_swap_roles_until_the_match_is_over() {
+    let mut versus = VersusMatch::new(["Ada".to_string(), "Bob".to_string()], 1);
+
+    // Ada dies once, then Bob's obstacle stops the replay on the first try
+    versus.deaths = 1;
+    assert!(!versus.end_turn());
+    assert_eq!(versus.scores, [RUN_POINTS - 1, DEFEND_POINTS]);
+    assert_eq!(versus.runner, 1);
+
+    // Bob makes it without dying, but the replay gets past Ada's obstacle twice
+    versus.replays_past = 2;
+    assert!(versus.end_turn());
+    assert_eq!(
+        versus.scores,
+        [
+            RUN_POINTS - 1 + DEFEND_POINTS - 2,
+            DEFEND_POINTS + RUN_POINTS
+        ]
+    );
+    assert_eq!(versus.winner(), Some(1));
+}
+
+#[test]
+fn generated_courses_are_the_same_for_a_seed() {
+    for difficulty in [0., 0.5, 1.] {
+        let generator = LevelGenerator::new(COURSE_LENGTH, difficulty);
+        // Not the flat ground used when no course could be finished
+        let course = generator
+            .try_generate(1234)
+            .unwrap_or_else(|| panic!("no course generated at difficulty {difficulty}"));
+
+        assert_eq!(course, generator.generate(1234));
+        assert_ne!(course, generator.generate(1235));
+        assert!(course.length >= COURSE_LENGTH);
+    }
+}
+
+/// Courses are only checked against [`JumpPhysics`], so it has to jump like the player does.
+#[test]
+fn the_jump_model_reaches_as_far_as_the_player() {
+    let mut level = flat_level(Vec::new());
+    level.length = 120;
+    level.ground[0].width = 120;
+    level.goal = (110, 3);
+    let mut app = HeadlessAppBuilder::default().with_level(level).build();
+    app.start_run();
+    // Up to speed first, like the running jumps of the model
+    app.hold(KeyCode::ArrowRight);
+    app.tick(60);
+    let takeoff = app.player_position().unwrap();
+
+    app.hold(KeyCode::Space);
+    let mut highest = takeoff.y;
+    let mut landing = None;
+    for _ in 0..200 {
+        app.tick(1);
+        let position = app.player_position().unwrap();
+        highest = highest.max(position.y);
+        if highest > takeoff.y + 1. && position.y <= takeoff.y + 0.5 {
+            landing = Some(position);
+            break;
+        }
+    }
+    let landing = landing.expect("still in the air after 200 ticks");
+
+    let model = JumpPhysics::default().reach();
+    let player = (
+        ((highest - takeoff.y) / TILE_SIZE) as i32,
+        ((landing.x - takeoff.x) / TILE_SIZE) as i32,
+    );
+    assert!(
+        (model.0 - player.0).abs() <= 1 && (model.1 - player.1).abs() <= 1,
+        "the model jumps {model:?} tiles (height, distance), the player {player:?}"
+    );
+    assert_eq!(app.outcomes().deaths, 0);
+}
+
+#[test]
+fn gaps_longer_than_a_jump_cant_be_solved() {
+    let physics = JumpPhysics::default();
+    let (_, distance) = physics.reach();
+    let gap = |width: u32| {
+        let mut level = flat_level(Vec::new());
+        level.ground = vec![
+            TileRect {
+                x: 0,
+                y: 0,
+                width: 10,
+                height: 3,
+            },
+            TileRect {
+                x: 10 + width as i32,
+                y: 0,
+                width: 30 - width,
+                height: 3,
+            },
+        ];
+        level
+    };
+
+    assert!(physics.is_solvable(&flat_level(Vec::new())));
+    assert!(physics.is_solvable(&gap(distance as u32 - 2)));
+    assert!(!physics.is_solvable(&gap(distance as u32 + 3)));
+}
+
+#[test]
+fn going_back_to_the_menu_clears_the_game() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+    app.hold(KeyCode::ArrowRight);
+    let reached = app.tick_until(MAX_TICKS, |outcomes| outcomes.goals_reached > 0);
+    assert!(reached, "goal not reached in {MAX_TICKS} ticks");
+
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Menu);
+    app.tick(2);
+
+    assert_eq!(app.player_position(), None);
+    let mut geometry = app.world_mut().query_filtered::<(), With<LevelGeometry>>();
+    assert_eq!(geometry.iter(app.world()).count(), 0);
+    assert_eq!(
+        *app.world().resource::<State<GameMode>>().get(),
+        GameMode::Survive
+    );
+
+    // Playing again spawns a single player
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Game);
+    app.tick(1);
+    assert!(app.player_position().is_some());
+}
+
+#[test]
+fn going_back_to_the_menu_removes_the_obstacles_bought() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+    app.hold(KeyCode::ArrowRight);
+    let reached = app.tick_until(MAX_TICKS, |outcomes| outcomes.goals_reached > 0);
+    assert!(reached, "goal not reached in {MAX_TICKS} ticks");
+    app.release(KeyCode::ArrowRight);
+    app.tick(1);
+    // Enough for a laser, which comes with a shadow
+    app.world_mut().resource_mut::<BuildPoints>().0 = 3;
+    app.tap(KeyCode::Digit2);
+    let mut shadows = app.world_mut().query_filtered::<(), With<FakeLaser>>();
+    assert_eq!(shadows.iter(app.world()).count(), 1);
+
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Menu);
+    app.tick(2);
+
+    let mut obstacles = app.world_mut().query_filtered::<(), With<ObstacleMarker>>();
+    assert_eq!(obstacles.iter(app.world()).count(), 0);
+    assert_eq!(shadows.iter(app.world()).count(), 0);
+}
+
+#[test]
+fn clearing_a_campaign_level_unlocks_the_next_one() {
+    let mut progress = CampaignProgress::default();
+    assert!(progress.is_unlocked(0));
+    assert!(!progress.is_unlocked(1));
+
+    assert!(progress.record_score(0, 200));
+    assert!(!progress.record_score(0, 100));
+    assert_eq!(progress.best_score(0), Some(200));
+    progress.clear(0);
+    assert!(progress.is_unlocked(1));
+    assert!(!progress.is_unlocked(2));
+
+    // Playing an earlier level again doesn't lock anything
+    progress.clear(1);
+    progress.clear(0);
+    assert!(progress.is_unlocked(2));
+    assert_eq!(progress.best_score(1), None);
+}
+
+#[test]
+fn campaign_levels_can_be_finished() {
+    let physics = JumpPhysics::default();
+    for level in CAMPAIGN {
+        let file = std::fs::read_to_string(format!("assets/{}", level.path)).unwrap();
+        let level: LevelAsset = ron::from_str(&file).unwrap();
+        assert!(physics.is_solvable(&level), "can't finish {}", level.name);
+    }
+}
+
+#[test]
+fn restarting_the_loop_takes_back_what_it_bought() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+    let start = app.player_position().unwrap();
+    app.hold(KeyCode::ArrowRight);
+    let reached = app.tick_until(MAX_TICKS, |outcomes| outcomes.goals_reached > 0);
+    assert!(reached, "goal not reached in {MAX_TICKS} ticks");
+    app.release(KeyCode::ArrowRight);
+    app.tick(1);
+    app.tap(KeyCode::Digit1);
+
+    app.world_mut().send_event(RestartLoop);
+    app.tick(2);
+
+    assert_eq!(
+        *app.world().resource::<State<GameMode>>().get(),
+        GameMode::Survive
+    );
+    assert_eq!(app.world().resource::<BuildPoints>().0, 0);
+    let mut obstacles = app.world_mut().query::<&ObstacleType>();
+    assert_eq!(obstacles.iter(app.world()).count(), 0);
+    let position = app.player_position().unwrap();
+    assert!((position.x - start.x).abs() < 1., "not back at the start");
+    assert_eq!(app.outcomes().deaths, 0);
+}
+
+#[test]
+fn a_laser_the_replay_got_past_is_aimed_again_where_it_is_moved() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+    app.hold(KeyCode::ArrowRight);
+    let reached = app.tick_until(MAX_TICKS, |outcomes| outcomes.goals_reached > 0);
+    assert!(reached, "goal not reached in {MAX_TICKS} ticks");
+    app.release(KeyCode::ArrowRight);
+    app.tick(1);
+    app.world_mut().resource_mut::<BuildPoints>().0 = 3;
+    app.tap(KeyCode::Digit2);
+
+    // Out of the replay's way, so it gets past
+    let mut laser = app
+        .world_mut()
+        .query_filtered::<(&mut Transform, &Flicker), With<ObstacleType>>();
+    laser.single_mut(app.world_mut()).unwrap().0.translation.x = 10_000.;
+    app.click();
+    app.tap(KeyCode::Space);
+    let got_past = app.tick_until(MAX_TICKS, |outcomes| outcomes.goals_reached > 1);
+    assert!(got_past, "the replay didn't reach the goal");
+    app.tick(1);
+
+    // Halfway along the replay this time, where it first goes through the laser
+    let positions = replay_of(&app).positions;
+    let x = positions[positions.len() / 2].1.x;
+    let (first_frame, _, _) = *positions
+        .iter()
+        .find(|(_, position, _)| (position.x - x).abs() <= 15.)
+        .unwrap();
+    laser.single_mut(app.world_mut()).unwrap().0.translation.x = x;
+    app.click();
+
+    let (_, flicker) = laser.single(app.world()).unwrap();
+    assert!(flicker.is_active_at(first_frame), "not aimed at the replay");
+    assert!(!flicker.is_active_at(first_frame - 1));
+}
+
+#[test]
+fn a_laser_being_placed_keeps_its_timing_without_scrolling() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+    app.hold(KeyCode::ArrowRight);
+    let reached = app.tick_until(MAX_TICKS, |outcomes| outcomes.goals_reached > 0);
+    assert!(reached, "goal not reached in {MAX_TICKS} ticks");
+    app.release(KeyCode::ArrowRight);
+    app.tick(1);
+    app.world_mut().resource_mut::<BuildPoints>().0 = 3;
+    app.tap(KeyCode::Digit2);
+
+    let mut ghost = app
+        .world_mut()
+        .query_filtered::<&Flicker, With<GhostObstacle>>();
+    let mut timing = |app: &App| -> Vec<bool> {
+        let flicker = ghost.single(app.world()).unwrap();
+        (0..600).map(|frame| flicker.is_active_at(frame)).collect()
+    };
+    let before = timing(&app);
+
+    // Holding shift or control changes the period or the duration, but only when scrolling
+    for modifier in [None, Some(KeyCode::ShiftLeft), Some(KeyCode::ControlLeft)] {
+        if let Some(key) = modifier {
+            app.hold(key);
+        }
+        app.tick(1);
+        assert_eq!(timing(&app), before, "changed while holding {modifier:?}");
+        if let Some(key) = modifier {
+            app.release(key);
+        }
+    }
+}
+
+/// The run being played, as it would be saved.
+fn replay_of(app: &App) -> ReplayFile {
+    let world = app.world();
+    ReplayFile::new(
+        world.resource::<LevelId>(),
+        1. / world.resource::<Time<Fixed>>().timestep().as_secs_f64(),
+        world.resource::<GameRng>().seed(),
+        world.resource::<RecordedPositions>(),
+        world.resource::<RecordedMovements>(),
+    )
+}
+
+#[test]
+fn loading_a_replay_restores_the_saved_run() {
+    let mut app = HeadlessAppBuilder::default().build();
+    app.start_run();
+    app.hold(KeyCode::ArrowRight);
+    let reached = app.tick_until(MAX_TICKS, |outcomes| outcomes.goals_reached > 0);
+    assert!(reached, "goal not reached in {MAX_TICKS} ticks");
+    app.release(KeyCode::ArrowRight);
+    app.tick(1);
+
+    // Someone else's run: shorter, with another seed
+    let mut shared = replay_of(&app);
+    shared.positions.truncate(10);
+    shared.inputs.truncate(10);
+    shared.header.seed = 7;
+    // Unique, so test runs going on at the same time don't share it
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!(
+        "gmtk-test-shared-{}-{nanos}.replay.ron",
+        std::process::id()
+    ));
+    shared.save(&path).unwrap();
+
+    app.world_mut().send_event(LoadReplay(path.clone()));
+    app.tick(1);
+
+    let loaded = replay_of(&app);
+    assert_eq!(loaded.positions, shared.positions);
+    assert_eq!(loaded.inputs, shared.inputs);
+    assert_eq!(loaded.header.seed, 7);
+
+    // Recorded at another tick rate, it would fall out of sync
+    let mut faster = shared.clone();
+    faster.header.tick_rate *= 2.;
+    faster.positions.truncate(5);
+    faster.save(&path).unwrap();
+    app.world_mut().send_event(LoadReplay(path.clone()));
+    app.tick(1);
+    assert_eq!(replay_of(&app).positions, shared.positions);
+
+    let mut outdated = shared;
+    outdated.header.format_version = REPLAY_FORMAT_VERSION - 1;
+    outdated.save(&path).unwrap();
+    assert!(matches!(
+        ReplayFile::load(&path),
+        Err(ReplayError::UnsupportedFormat { found }) if found == REPLAY_FORMAT_VERSION - 1
+    ));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn rebinding_a_key_swaps_it_with_the_action_using_it() {
+    let mut input_map = InputMap::default();
+    input_map.rebind(Action::MoveLeft, KeyCode::KeyD);
+
+    // The arrow keys still work, and moving right gets the key moving left had
+    assert_eq!(
+        input_map.keys(Action::MoveLeft),
+        [KeyCode::KeyD, KeyCode::ArrowLeft]
+    );
+    assert_eq!(
+        input_map.keys(Action::MoveRight),
+        [KeyCode::KeyA, KeyCode::ArrowRight]
+    );
+    // The camera only moves while placing obstacles, so it can share keys with the player
+    assert_eq!(
+        input_map.keys(Action::CameraRight),
+        [KeyCode::KeyD, KeyCode::ArrowRight]
+    );
+}